@@ -0,0 +1,229 @@
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::rc::Rc;
+
+use futures::channel::oneshot;
+use futures::future::{self, Either, FutureExt, LocalBoxFuture, Shared};
+use gloo_timers::future::TimeoutFuture;
+use wasm_bindgen::prelude::*;
+
+use crate::{session, WormholeError};
+
+#[wasm_bindgen]
+#[derive(Clone)]
+/// A token that can be used to cancel a pending operation, like a `connect` call or a transfer.
+///
+/// A single token can be passed to multiple operations, in which case cancelling it cancels all of them.
+/// Until it is cancelled, a token can be reused for any number of operations. Once cancelled, it stays cancelled,
+/// so operations it is passed to afterwards are cancelled right away. Call `free()` on tokens that are no longer needed.
+pub struct CancelToken {
+    sender: Rc<RefCell<Option<oneshot::Sender<()>>>>,
+    receiver: Shared<oneshot::Receiver<()>>,
+}
+
+impl Default for CancelToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl CancelToken {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        let (sender, receiver) = oneshot::channel();
+
+        Self {
+            sender: Rc::new(RefCell::new(Some(sender))),
+            receiver: receiver.shared(),
+        }
+    }
+
+    #[wasm_bindgen]
+    /// Cancels the operations that this token was passed to.
+    ///
+    /// Pending operations reject with a cancellation error. Cancelling after an operation completed, or cancelling
+    /// the token again, is a no-op.
+    pub fn cancel(&self) {
+        if let Some(sender) = self.sender.borrow_mut().take() {
+            let _ = sender.send(());
+        }
+    }
+
+    #[wasm_bindgen]
+    /// Whether the token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.sender.borrow().is_none()
+    }
+
+    #[wasm_bindgen]
+    /// Whether the token was passed to an operation that is still in flight, i.e. cancelling it would have an effect.
+    pub fn is_active(&self) -> bool {
+        !self.is_cancelled() && session::is_registered(self)
+    }
+}
+
+impl CancelToken {
+    /// Whether the two tokens are clones of each other.
+    pub(crate) fn same_as(&self, other: &CancelToken) -> bool {
+        Rc::ptr_eq(&self.sender, &other.sender)
+    }
+}
+
+/// Returns a future that resolves once the token (if any) is cancelled.
+pub(crate) fn cancelled(token: Option<&CancelToken>) -> impl Future<Output = ()> + 'static {
+    let receiver = token.map(|token| token.receiver.clone());
+
+    async move {
+        match receiver {
+            // The sender only goes away without sending once every clone of the token is dropped,
+            // in which case the token can no longer be cancelled.
+            Some(receiver) if receiver.await.is_ok() => (),
+            _ => future::pending().await,
+        }
+    }
+}
+
+/// Races the future against the token (if any), dropping the future if the token is cancelled first.
+pub(crate) async fn cancellable<F, T, E>(future: F, token: Option<&CancelToken>) -> Result<T, WormholeError>
+where
+    F: Future<Output = Result<T, E>>,
+    WormholeError: From<E>,
+{
+    match future::select(Box::pin(future), Box::pin(cancelled(token))).await {
+        Either::Left((result, _)) => Ok(result?),
+        Either::Right(_) => Err(WormholeError::Cancelled),
+    }
+}
+
+/// Runs a transfer with a cancellation future for the token (if any), failing with `Cancelled` if that future resolved
+/// during the transfer.
+///
+/// When cancelled, the transfer functions notify the peer and then return successfully, so whether the cancellation
+/// took effect has to be recorded. Cancelling the token after the transfer completed doesn't affect the result.
+pub(crate) async fn cancellable_transfer<F, Fut, T, E>(transfer: F, token: Option<&CancelToken>) -> Result<T, WormholeError>
+where
+    F: FnOnce(LocalBoxFuture<'static, ()>) -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    WormholeError: From<E>,
+{
+    let fired = Rc::new(Cell::new(false));
+    let cancel = cancelled(token);
+    let fire = fired.clone();
+    let cancel = async move {
+        cancel.await;
+        fire.set(true);
+    };
+    let result = transfer(cancel.boxed_local()).await;
+
+    if fired.get() {
+        return Err(WormholeError::Cancelled);
+    }
+    Ok(result?)
+}
+
+/// Races the future against a timer (if any), dropping the future if the timer expires first.
+pub(crate) async fn with_timeout<F, T>(future: F, timeout_ms: Option<f64>) -> Result<T, WormholeError>
+where
+    F: Future<Output = Result<T, WormholeError>>,
+{
+    let Some(timeout_ms) = timeout_ms else {
+        return future.await;
+    };
+
+    match future::select(Box::pin(future), Box::pin(TimeoutFuture::new(timeout_ms as u32))).await {
+        Either::Left((result, _)) => result,
+        Either::Right(_) => Err(WormholeError::Timeout),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::*;
+    use crate::session::Session;
+
+    #[test]
+    fn cancelling_twice_is_a_no_op() {
+        let token = CancelToken::new();
+        token.cancel();
+        token.cancel();
+
+        assert!(token.is_cancelled());
+        assert!(!token.is_active());
+    }
+
+    #[test]
+    fn clones_share_the_cancellation() {
+        let token = CancelToken::new();
+        let clone = token.clone();
+        clone.cancel();
+
+        assert!(token.is_cancelled());
+    }
+
+    #[test]
+    fn cancelling_after_completion_is_a_no_op() {
+        let token = CancelToken::new();
+        let session = Session::register(Some(&token));
+        let result = block_on(cancellable(future::ready(Ok::<_, WormholeError>(42)), session.token()));
+        drop(session);
+
+        token.cancel();
+        assert!(matches!(result, Ok(42)));
+        assert!(token.is_cancelled());
+        assert!(!token.is_active());
+    }
+
+    #[test]
+    fn cancelled_tokens_cancel_later_operations_right_away() {
+        let token = CancelToken::new();
+        token.cancel();
+
+        let result = block_on(cancellable(future::pending::<Result<(), WormholeError>>(), Some(&token)));
+        assert!(matches!(result, Err(WormholeError::Cancelled)));
+    }
+
+    #[test]
+    fn transfers_that_saw_the_cancellation_are_cancelled() {
+        let token = CancelToken::new();
+        token.cancel();
+
+        let result = block_on(cancellable_transfer(
+            |cancelled| async {
+                // Like the transfer functions, which notify the peer and then return successfully.
+                cancelled.await;
+                Ok::<_, WormholeError>(42)
+            },
+            Some(&token),
+        ));
+        assert!(matches!(result, Err(WormholeError::Cancelled)));
+    }
+
+    #[test]
+    fn transfers_cancelled_too_late_succeed() {
+        let token = CancelToken::new();
+
+        let result = block_on(cancellable_transfer(
+            |_| async {
+                token.cancel();
+                Ok::<_, WormholeError>(42)
+            },
+            Some(&token),
+        ));
+        assert!(matches!(result, Ok(42)));
+        assert!(token.is_cancelled());
+    }
+
+    #[test]
+    fn unused_tokens_can_be_reused() {
+        let token = CancelToken::new();
+        for value in [1, 2] {
+            let result = block_on(cancellable(future::ready(Ok::<_, WormholeError>(value)), Some(&token)));
+            assert!(matches!(result, Ok(v) if v == value));
+        }
+
+        assert!(!token.is_cancelled());
+    }
+}
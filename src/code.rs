@@ -0,0 +1,380 @@
+use std::fmt;
+
+use magic_wormhole::{Code, Nameplate};
+use rand::rngs::OsRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use sha2::{Digest, Sha256};
+use wasm_bindgen::prelude::*;
+
+use crate::wordlist;
+use crate::WormholeError;
+
+/// The minimum number of words in a code.
+pub(crate) const MIN_CODE_LENGTH: usize = 1;
+/// The maximum number of words in a code.
+pub(crate) const MAX_CODE_LENGTH: usize = 32;
+
+/// The minimum number of words for a code to be considered safe by `is_code_length_safe`.
+const MIN_SAFE_CODE_LENGTH: usize = 2;
+
+/// The largest nameplate that `generate_code` picks.
+const MAX_GENERATED_NAMEPLATE: u32 = 999;
+
+/// Checks that the code length is within the supported range.
+pub(crate) fn validate_code_length(code_length: usize) -> Result<(), WormholeError> {
+    if (MIN_CODE_LENGTH..=MAX_CODE_LENGTH).contains(&code_length) {
+        Ok(())
+    } else {
+        Err(WormholeError::InvalidCodeLength(code_length))
+    }
+}
+
+#[wasm_bindgen]
+/// Generates a wormhole code (e.g. `7-guitarist-revenge`), without connecting to the rendezvous server.
+///
+/// The nameplate is picked at random between 1 and 999, rather than allocated by the server. Connect with
+/// `Wormhole.connect_with_code` to claim it. Another client may already be using the same nameplate, in which case
+/// the handshake fails (usually with `WrongCode`), and a new code should be generated.
+///
+/// # Arguments
+///
+/// * `code_length` - The number of words to generate, between 1 and 32. 2 is recommended, see `is_code_length_safe`.
+pub fn generate_code(code_length: usize) -> Result<String, WormholeError> {
+    generate(code_length, &mut OsRng)
+}
+
+#[wasm_bindgen]
+/// Deterministically derives a wormhole code from a seed, like `generate_code`.
+///
+/// The same seed and code length always yield the same code. This is only meant for tests and controlled
+/// environments: anyone who can guess the seed can guess the code, so never derive it from predictable data in
+/// production. Use `generate_code` otherwise.
+///
+/// # Arguments
+///
+/// * `code_length` - The number of words to generate.
+/// * `seed` - The seed to derive the words from.
+pub fn generate_code_seeded(code_length: usize, seed: &[u8]) -> Result<String, WormholeError> {
+    let mut rng = ChaCha20Rng::from_seed(Sha256::digest(seed).into());
+
+    generate(code_length, &mut rng)
+}
+
+/// Picks the nameplate and the password words for a code of the given length.
+fn generate(code_length: usize, rng: &mut impl Rng) -> Result<String, WormholeError> {
+    validate_code_length(code_length)?;
+
+    let nameplate = rng.gen_range(1..=MAX_GENERATED_NAMEPLATE);
+    let words: Vec<&str> = (0..code_length)
+        .map(|position| *wordlist::words_at(position).choose(rng).expect("the word lists are not empty"))
+        .collect();
+
+    Ok(format!("{nameplate}-{}", words.join("-")))
+}
+
+#[wasm_bindgen]
+/// Returns the entropy of a generated code with the given number of words, in bits.
+///
+/// Each word is picked uniformly from the word list of its position, so it contributes the binary logarithm of the
+/// size of that list (8 bits for the 256-word lists).
+///
+/// The nameplate is deliberately left out: it is allocated by the rendezvous server and listed to anyone connected
+/// to it, so it adds no secrecy. An attacker who knows the nameplate still has to guess the words, and gets a single
+/// guess per connection attempt, which the peers notice as a `WrongCode` failure.
+///
+/// # Arguments
+///
+/// * `code_length` - The number of words in the code, between 1 and 32.
+pub fn code_entropy_bits(code_length: usize) -> Result<f64, WormholeError> {
+    validate_code_length(code_length)?;
+
+    Ok((0..code_length).map(|position| (wordlist::words_at(position).len() as f64).log2()).sum())
+}
+
+#[wasm_bindgen]
+/// Whether codes with the given number of words are safe enough for general use.
+///
+/// This is the case from two words (16 bits) on, which is the default of the standard client: a single guess then
+/// has a 1 in 65536 chance of succeeding. One-word codes are only suitable for low-risk transfers.
+///
+/// # Arguments
+///
+/// * `code_length` - The number of words in the code.
+pub fn is_code_length_safe(code_length: usize) -> bool {
+    (MIN_SAFE_CODE_LENGTH..=MAX_CODE_LENGTH).contains(&code_length)
+}
+
+#[wasm_bindgen]
+/// Returns the words that can appear at the given position of the password part of a code.
+///
+/// Codes alternate between two word lists, so the suggestions for autocompleting a word depend on its position.
+///
+/// # Arguments
+///
+/// * `position` - The zero-based position of the word, not counting the nameplate.
+pub fn default_wordlist(position: usize) -> Vec<String> {
+    wordlist::words_at(position).iter().map(|&word| word.into()).collect()
+}
+
+#[wasm_bindgen]
+#[derive(Clone)]
+/// A wormhole code, split into its nameplate and password words.
+pub struct ParsedCode {
+    nameplate: String,
+    words: Vec<String>,
+}
+
+impl ParsedCode {
+    /// Builds the wormhole code directly from the nameplate and the words.
+    pub(crate) fn to_code(&self) -> Code {
+        Code::new(&Nameplate::new(&self.nameplate), &self.words.join("-"))
+    }
+}
+
+impl fmt::Display for ParsedCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.nameplate, self.words.join("-"))
+    }
+}
+
+#[wasm_bindgen]
+impl ParsedCode {
+    #[wasm_bindgen(getter)]
+    pub fn nameplate(&self) -> String {
+        self.nameplate.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn words(&self) -> Vec<String> {
+        self.words.clone()
+    }
+}
+
+/// Invisible characters that are stripped from codes, as they tend to sneak in when copy-pasting.
+const ZERO_WIDTH_CHARS: [char; 5] = ['\u{200B}', '\u{200C}', '\u{200D}', '\u{2060}', '\u{FEFF}'];
+/// Quotes that are stripped from around codes, e.g. when copy-pasting them from a chat message.
+const QUOTE_CHARS: [char; 7] = ['"', '\'', '`', '‘', '’', '“', '”'];
+
+#[wasm_bindgen]
+/// Normalizes a user-entered wormhole code, the same way `parse_code` and `Wormhole.connect_with_code` do.
+///
+/// Zero-width characters are removed, surrounding whitespace and quotes are trimmed, and the words are lowercased.
+///
+/// # Arguments
+///
+/// * `code` - The wormhole code, as entered by the user.
+pub fn normalize_code(code: &str) -> String {
+    let code: String = code.chars().filter(|c| !ZERO_WIDTH_CHARS.contains(c)).collect();
+
+    code.trim_matches(|c: char| c.is_whitespace() || QUOTE_CHARS.contains(&c)).to_lowercase()
+}
+
+/// Whether the string is a well-formed nameplate.
+fn is_nameplate(nameplate: &str) -> bool {
+    !nameplate.is_empty() && nameplate.chars().all(|c| c.is_ascii_digit())
+}
+
+#[wasm_bindgen]
+/// Parses a wormhole code (e.g. `7-guitarist-revenge`) into its nameplate and password words.
+///
+/// # Arguments
+///
+/// * `code` - The wormhole code. It is normalized with `normalize_code` first.
+pub fn parse_code(code: &str) -> Result<ParsedCode, WormholeError> {
+    let invalid = |reason: &str| WormholeError::InvalidCode {
+        code: code.into(),
+        reason: reason.into(),
+    };
+    let normalized = normalize_code(code);
+    let (nameplate, password) = normalized
+        .split_once('-')
+        .ok_or_else(|| invalid("expected the nameplate and the words to be separated by `-`"))?;
+
+    if !is_nameplate(nameplate) {
+        return Err(invalid("the nameplate must be a number"));
+    }
+    let words: Vec<String> = password.split('-').map(String::from).collect();
+    if words.iter().any(String::is_empty) {
+        return Err(invalid("the words must not be empty"));
+    }
+
+    Ok(ParsedCode {
+        nameplate: nameplate.into(),
+        words,
+    })
+}
+
+#[wasm_bindgen]
+/// Diagnostics about a (possibly partially entered) wormhole code, as returned by `validate_code`.
+pub struct CodeValidation {
+    has_nameplate: bool,
+    word_count: usize,
+    error: Option<String>,
+}
+
+#[wasm_bindgen]
+impl CodeValidation {
+    #[wasm_bindgen(getter)]
+    /// Whether the code is well-formed, i.e. `parse_code` (and `Wormhole.connect_with_code`) would accept it.
+    pub fn is_valid(&self) -> bool {
+        self.error.is_none()
+    }
+
+    #[wasm_bindgen(getter)]
+    /// Whether the code starts with a well-formed nameplate.
+    pub fn has_nameplate(&self) -> bool {
+        self.has_nameplate
+    }
+
+    #[wasm_bindgen(getter)]
+    /// The number of non-empty words following the nameplate.
+    pub fn word_count(&self) -> usize {
+        self.word_count
+    }
+
+    #[wasm_bindgen(getter)]
+    /// Why the code is invalid, if it is.
+    pub fn error(&self) -> Option<String> {
+        self.error.clone()
+    }
+}
+
+#[wasm_bindgen]
+/// Validates a wormhole code, returning diagnostics that can be used to show hints as the user types.
+///
+/// Unlike `parse_code`, this never fails.
+///
+/// # Arguments
+///
+/// * `code` - The (possibly partially entered) wormhole code. It is normalized with `normalize_code` first.
+pub fn validate_code(code: &str) -> CodeValidation {
+    let normalized = normalize_code(code);
+    let mut parts = normalized.split('-');
+    let has_nameplate = parts.next().is_some_and(is_nameplate);
+    let word_count = if has_nameplate { parts.filter(|word| !word.is_empty()).count() } else { 0 };
+
+    CodeValidation {
+        has_nameplate,
+        word_count,
+        error: parse_code(code).err().map(|e| e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_code_trims_whitespace() {
+        assert_eq!(normalize_code("  7-guitarist-revenge \n"), "7-guitarist-revenge");
+    }
+
+    #[test]
+    fn normalize_code_lowercases() {
+        assert_eq!(normalize_code("7-Guitarist-REVENGE"), "7-guitarist-revenge");
+    }
+
+    #[test]
+    fn normalize_code_strips_zero_width_chars() {
+        assert_eq!(normalize_code("\u{FEFF}7-guitar\u{200B}ist-revenge\u{2060}"), "7-guitarist-revenge");
+    }
+
+    #[test]
+    fn normalize_code_strips_quotes() {
+        assert_eq!(normalize_code("“7-guitarist-revenge”"), "7-guitarist-revenge");
+        assert_eq!(normalize_code(" '7-guitarist-revenge' "), "7-guitarist-revenge");
+        assert_eq!(normalize_code("`7-guitarist-revenge`"), "7-guitarist-revenge");
+    }
+
+    #[test]
+    fn parse_code_normalizes_first() {
+        let parsed = parse_code(" \"7-Guitarist-Revenge \" ").unwrap();
+
+        assert_eq!(parsed.nameplate(), "7");
+        assert_eq!(parsed.words(), ["guitarist", "revenge"]);
+    }
+
+    #[test]
+    fn generate_code_seeded_is_deterministic() {
+        assert_eq!(generate_code_seeded(4, b"seed").unwrap(), generate_code_seeded(4, b"seed").unwrap());
+    }
+
+    #[test]
+    fn generate_code_seeded_depends_on_the_seed() {
+        assert_ne!(generate_code_seeded(4, b"seed").unwrap(), generate_code_seeded(4, b"other seed").unwrap());
+    }
+
+    #[test]
+    fn generate_code_seeded_uses_the_word_lists() {
+        let words = parse_code(&generate_code_seeded(6, b"seed").unwrap()).unwrap().words();
+
+        assert_eq!(words.len(), 6);
+        for (position, word) in words.iter().enumerate() {
+            assert!(wordlist::words_at(position).contains(&word.as_str()), "`{word}` is not a word at position {position}");
+        }
+    }
+
+    #[test]
+    fn generated_codes_start_with_a_nameplate() {
+        for seed in [b"seed".as_slice(), b"other seed", b"yet another seed"] {
+            let parsed = parse_code(&generate_code_seeded(2, seed).unwrap()).unwrap();
+            let nameplate: u32 = parsed.nameplate().parse().unwrap();
+
+            assert!((1..=MAX_GENERATED_NAMEPLATE).contains(&nameplate), "{nameplate}");
+        }
+    }
+
+    #[test]
+    fn default_wordlist_matches_the_pgp_word_list() {
+        let odd = default_wordlist(0);
+        assert_eq!(odd.len(), 256);
+        assert_eq!([&odd[0x00], &odd[0x69], &odd[0xff]], ["adroitness", "guitarist", "yucatan"]);
+
+        let even = default_wordlist(1);
+        assert_eq!(even.len(), 256);
+        assert_eq!([&even[0x00], &even[0x69], &even[0xff]], ["aardvark", "gazelle", "zulu"]);
+
+        assert_eq!(default_wordlist(2), odd);
+    }
+
+    #[test]
+    fn code_lengths_outside_the_range_are_rejected_with_the_range() {
+        for code_length in [0, 33] {
+            let error = generate_code(code_length).unwrap_err();
+            assert!(matches!(error, WormholeError::InvalidCodeLength(length) if length == code_length));
+            assert_eq!(error.to_string(), format!("Invalid code length {code_length}: must be between 1 and 32"));
+        }
+    }
+
+    #[test]
+    fn code_lengths_within_the_range_are_accepted() {
+        assert_eq!(parse_code(&generate_code(1).unwrap()).unwrap().words().len(), 1);
+        assert_eq!(parse_code(&generate_code(32).unwrap()).unwrap().words().len(), 32);
+    }
+
+    #[test]
+    fn code_entropy_bits_counts_the_words_only() {
+        let bits_per_word = (wordlist::EVEN_WORDS.len() as f64).log2();
+
+        assert_eq!(bits_per_word, 8.0);
+        assert_eq!(code_entropy_bits(1).unwrap(), bits_per_word);
+        assert_eq!(code_entropy_bits(2).unwrap(), 2.0 * bits_per_word);
+        assert_eq!(code_entropy_bits(MAX_CODE_LENGTH).unwrap(), MAX_CODE_LENGTH as f64 * bits_per_word);
+    }
+
+    #[test]
+    fn code_entropy_bits_rejects_unsupported_lengths() {
+        assert!(code_entropy_bits(0).is_err());
+        assert!(code_entropy_bits(MAX_CODE_LENGTH + 1).is_err());
+        assert!(code_entropy_bits(usize::MAX).is_err());
+    }
+
+    #[test]
+    fn parse_code_rejects_malformed_codes() {
+        assert!(parse_code("guitarist-revenge").is_err());
+        assert!(parse_code("7").is_err());
+        assert!(parse_code("7-guitarist--revenge").is_err());
+    }
+}
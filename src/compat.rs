@@ -0,0 +1,137 @@
+use wasm_bindgen::prelude::*;
+
+use crate::config::PROTOCOL_VERSION_KEY;
+use crate::{json_to_js, AppConfig, WormholeError};
+
+impl AppConfig {
+    /// Compares the protocol version of the peer with the local one, once the handshake completed.
+    ///
+    /// A mismatch fails in strict mode, and is only reported as a warning otherwise. Versions that either side
+    /// doesn't advertise are not compared.
+    pub(crate) fn check_peer_version(&self, peer_version: &serde_json::Value) -> Result<(), WormholeError> {
+        let (Some(local), Some(peer)) = (self.app_version.get(PROTOCOL_VERSION_KEY), peer_version.get(PROTOCOL_VERSION_KEY)) else {
+            return Ok(());
+        };
+        if local == peer {
+            return Ok(());
+        }
+
+        if self.strict_version_check {
+            return Err(WormholeError::IncompatiblePeer {
+                local: local.to_string(),
+                peer: peer.to_string(),
+            });
+        }
+        log::warn!("the peer speaks protocol version {peer}, which is incompatible with version {local}");
+        if let Some(callback) = &self.version_warning_callback {
+            // Exceptions thrown by the callback are deliberately ignored, so that they can't abort the connection.
+            let _ = callback.call1(&JsValue::NULL, &json_to_js(&serde_json::json!({ "local": local, "peer": peer })));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{default_app_id, default_rendezvous_url};
+
+    fn config(strict_version_check: bool) -> AppConfig {
+        let mut config = AppConfig::new_unchecked(default_app_id(), default_rendezvous_url());
+        config.set_strict_version_check(strict_version_check);
+
+        config
+    }
+
+    #[test]
+    fn matching_versions_are_compatible() {
+        let config = config(true);
+        let peer_version = config.app_version.clone();
+
+        assert!(config.check_peer_version(&peer_version).is_ok());
+    }
+
+    #[test]
+    fn mismatched_versions_fail_in_strict_mode() {
+        let result = config(true).check_peer_version(&json!({ PROTOCOL_VERSION_KEY: 2 }));
+
+        assert!(matches!(result, Err(WormholeError::IncompatiblePeer { local, peer }) if local == "1" && peer == "2"));
+    }
+
+    #[test]
+    fn mismatched_versions_are_only_warnings_in_lenient_mode() {
+        assert!(config(false).check_peer_version(&json!({ PROTOCOL_VERSION_KEY: 2 })).is_ok());
+    }
+
+    #[test]
+    fn unadvertised_versions_are_not_compared() {
+        assert!(config(true).check_peer_version(&json!({})).is_ok());
+
+        let mut config = config(true);
+        config.app_version = json!({});
+        assert!(config.check_peer_version(&json!({ PROTOCOL_VERSION_KEY: 2 })).is_ok());
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod peer_tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use js_sys::{Function, JSON};
+    use wasm_bindgen::closure::Closure;
+    use wasm_bindgen::{JsCast, JsValue};
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::test_support::local_config;
+    use crate::{AppConfig, Wormhole, WormholeErrorKind};
+
+    /// Returns a configuration that advertises the given protocol version.
+    fn config_speaking(protocol_version: u32, strict_version_check: bool) -> AppConfig {
+        let mut config = local_config();
+        config.set_app_version(JSON::parse(&format!(r#"{{ "protocol_version": {protocol_version} }}"#)).unwrap()).unwrap();
+        config.set_strict_version_check(strict_version_check);
+
+        config
+    }
+
+    #[wasm_bindgen_test]
+    #[ignore = "needs a local rendezvous server and transit relay, see `test_support`"]
+    async fn strict_peers_reject_incompatible_versions() {
+        let statuses = Rc::new(RefCell::new(Vec::new()));
+        let recorded = statuses.clone();
+        let callback = Closure::<dyn FnMut(JsValue)>::new(move |status: JsValue| {
+            recorded.borrow_mut().push(status.as_string().unwrap_or_default());
+        });
+        let mut config = config_speaking(1, true);
+        config.set_status_callback(Some(callback.as_ref().unchecked_ref::<Function>().clone()));
+
+        let welcome_and_handshake = Wormhole::connect_without_code(&config, 2, None, None, None).await.unwrap();
+        let code = welcome_and_handshake.welcome().code();
+
+        let (sender, receiver) = futures::join!(
+            welcome_and_handshake.finish(None),
+            Wormhole::connect_with_code(&config_speaking(2, false), &code, None, None, None, None)
+        );
+        assert_eq!(sender.err().unwrap().kind(), WormholeErrorKind::IncompatiblePeer);
+        assert!(receiver.is_ok());
+        assert_eq!(*statuses.borrow(), ["Connecting", "WaitingForPeer", "Failed"]);
+    }
+
+    #[wasm_bindgen_test]
+    #[ignore = "needs a local rendezvous server and transit relay, see `test_support`"]
+    async fn lenient_peers_accept_incompatible_versions() {
+        let welcome_and_handshake = Wormhole::connect_without_code(&config_speaking(1, false), 2, None, None, None).await.unwrap();
+        let code = welcome_and_handshake.welcome().code();
+
+        let (sender, receiver) = futures::join!(
+            welcome_and_handshake.finish(None),
+            Wormhole::connect_with_code(&config_speaking(2, false), &code, None, None, None, None)
+        );
+        assert!(sender.is_ok());
+        assert!(receiver.is_ok());
+    }
+}
@@ -0,0 +1,872 @@
+use js_sys::{Function, Promise};
+use magic_wormhole::rendezvous::DEFAULT_RENDEZVOUS_SERVER;
+use magic_wormhole::transfer::{AppVersion, APPID_RAW};
+use magic_wormhole::transit::{Abilities, RelayHint, DEFAULT_RELAY_SERVER};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::Response;
+
+use crate::{json_to_js, WormholeError};
+
+/// The key of the app version under which the capability flags are advertised.
+pub(crate) const ABILITIES_KEY: &str = "abilities";
+/// The key of the app version under which the device name is advertised.
+pub(crate) const DEVICE_NAME_KEY: &str = "device_name";
+/// The key of the app version under which the protocol version of the app is advertised, see `AppConfig.set_strict_version_check`.
+pub(crate) const PROTOCOL_VERSION_KEY: &str = "protocol_version";
+/// The protocol version that the bindings advertise by default, for their extensions of the transfer protocol
+/// (framed and JSON messages, capability flags and device names).
+const PROTOCOL_VERSION: u64 = 1;
+/// The maximum number of characters of an advertised device name.
+pub(crate) const MAX_DEVICE_NAME_LEN: usize = 64;
+/// The public websocket transit relay, which the WASM build falls back to, as the default relay server only speaks TCP.
+const DEFAULT_WEBSOCKET_RELAY_SERVER: &str = "wss://relay.mw.leastauthority.com";
+
+#[wasm_bindgen]
+extern "C" {
+    /// The global `fetch`, which is available in windows, workers and Node.js alike.
+    #[wasm_bindgen(js_name = fetch)]
+    fn fetch(url: &str) -> Promise;
+}
+
+#[wasm_bindgen]
+/// Returns the URL of the default public rendezvous server.
+pub fn default_rendezvous_url() -> String {
+    DEFAULT_RENDEZVOUS_SERVER.into()
+}
+
+#[wasm_bindgen]
+/// Returns the URL of the transit relay that is used if none is configured.
+///
+/// This is the default public relay server of the wormhole library natively. Browsers can't reach it, as it only
+/// speaks TCP, so the WASM build uses a public websocket relay instead.
+pub fn default_transit_relay_url() -> String {
+    if cfg!(target_arch = "wasm32") {
+        DEFAULT_WEBSOCKET_RELAY_SERVER.into()
+    } else {
+        DEFAULT_RELAY_SERVER.into()
+    }
+}
+
+#[wasm_bindgen]
+/// Returns the app ID of the standard file transfer protocol.
+pub fn default_app_id() -> String {
+    APPID_RAW.into()
+}
+
+/// Removes control characters from a device name, and limits its length.
+pub(crate) fn sanitize_device_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_DEVICE_NAME_LEN)
+        .collect::<String>()
+        .trim()
+        .into()
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// The transit connections that transfers are allowed to use.
+pub enum TransitMode {
+    /// Only connect directly to the peer. This never works in the WASM build, see `Wormhole.can_connect_directly`.
+    DirectOnly,
+    /// Only connect through a transit relay.
+    RelayOnly,
+    /// Use whichever connection works best.
+    #[default]
+    All,
+}
+
+impl From<TransitMode> for Abilities {
+    fn from(value: TransitMode) -> Self {
+        match value {
+            TransitMode::DirectOnly => Abilities::FORCE_DIRECT,
+            TransitMode::RelayOnly => Abilities::FORCE_RELAY,
+            TransitMode::All => Abilities::ALL_ABILITIES,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Serialize, Deserialize)]
+/// Wormhole configuration corresponding to an upper layer protocol
+///
+/// There are multiple different protocols built on top of the core Wormhole protocol.
+/// They are identified by a unique URI-like ID string, an URL to find the rendezvous server (might be shared among multiple protocols),
+/// and client implementations also have a “version” data to do protocol negotiation.
+pub struct AppConfig {
+    pub(crate) id: String,
+    pub(crate) rendezvous_url: String,
+    #[serde(default = "AppConfig::default_app_version")]
+    pub(crate) app_version: serde_json::Value,
+    #[serde(default)]
+    transit_relay_urls: Vec<String>,
+    #[serde(default)]
+    transit_mode: TransitMode,
+    #[serde(default)]
+    pub(crate) max_bytes_per_second: Option<f64>,
+    #[serde(default)]
+    pub(crate) require_verification: bool,
+    #[serde(default)]
+    pub(crate) strict_version_check: bool,
+    #[serde(skip)]
+    pub(crate) verification_callback: Option<Function>,
+    #[serde(skip)]
+    pub(crate) version_warning_callback: Option<Function>,
+    #[serde(skip)]
+    pub(crate) status_callback: Option<Function>,
+    #[serde(skip)]
+    pub(crate) motd_callback: Option<Function>,
+    #[serde(skip)]
+    pub(crate) transit_callback: Option<Function>,
+    #[serde(skip)]
+    pub(crate) event_callback: Option<Function>,
+}
+
+#[wasm_bindgen]
+impl AppConfig {
+    #[wasm_bindgen(constructor)]
+    /// Creates a new configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The app ID. Must be URI-like, e.g. `example.com/my-app`.
+    /// * `rendezvous_url` - The URL of the rendezvous server. Must use the `ws://` or `wss://` scheme.
+    pub fn new(id: String, rendezvous_url: String) -> Result<AppConfig, WormholeError> {
+        Self::validate_id(&id)?;
+        Self::validate_rendezvous_url(&rendezvous_url)?;
+
+        Ok(Self::new_unchecked(id, rendezvous_url))
+    }
+
+    #[wasm_bindgen]
+    /// Creates a new configuration, without validating the app ID and the rendezvous URL.
+    ///
+    /// This is an escape hatch for advanced use cases. Prefer using the regular constructor instead.
+    pub fn new_unchecked(id: String, rendezvous_url: String) -> Self {
+        Self {
+            id,
+            rendezvous_url,
+            app_version: Self::default_app_version(),
+            transit_relay_urls: Vec::new(),
+            transit_mode: TransitMode::default(),
+            max_bytes_per_second: None,
+            require_verification: false,
+            strict_version_check: false,
+            verification_callback: None,
+            version_warning_callback: None,
+            status_callback: None,
+            motd_callback: None,
+            transit_callback: None,
+            event_callback: None,
+        }
+    }
+
+    #[wasm_bindgen]
+    /// Creates a configuration for the standard file transfer protocol, using the default public rendezvous server.
+    pub fn new_default() -> Self {
+        Self::new_unchecked(default_app_id(), default_rendezvous_url())
+    }
+
+    #[wasm_bindgen]
+    /// Creates a configuration with the app ID `<base_id>/<namespace>`, using the default public rendezvous server.
+    ///
+    /// The rendezvous server only pairs clients with the same app ID, so apps (or deployments of an app) with different
+    /// namespaces never collide on codes, even when sharing a server. Both peers must use the same namespace, and only
+    /// peers with the same app ID (e.g. `default_app_id()` for the standard CLI client) can connect to each other.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_id` - The app ID to namespace, e.g. `example.com/my-app`.
+    /// * `namespace` - The namespace, e.g. `staging`. Must be a single path segment, i.e. not contain `/`.
+    pub fn with_namespace(base_id: &str, namespace: &str) -> Result<AppConfig, WormholeError> {
+        let id = format!("{}/{namespace}", base_id.trim_end_matches('/'));
+        if namespace.is_empty() || namespace.contains('/') {
+            return Err(WormholeError::AppId {
+                id,
+                reason: format!("invalid namespace `{namespace}`: expected a single, non-empty path segment"),
+            });
+        }
+
+        Self::new(id, default_rendezvous_url())
+    }
+
+    #[wasm_bindgen]
+    /// Restores a configuration that was serialized with `to_json`.
+    ///
+    /// The restored configuration is validated the same way as with the regular constructor.
+    ///
+    /// # Arguments
+    ///
+    /// * `json` - The serialized configuration.
+    pub fn from_json(json: &str) -> Result<AppConfig, WormholeError> {
+        let config: Self = serde_json::from_str(json).map_err(|e| WormholeError::InvalidConfig(e.to_string()))?;
+
+        Self::validate_id(&config.id)?;
+        Self::validate_rendezvous_url(&config.rendezvous_url)?;
+        for relay_url in &config.transit_relay_urls {
+            Self::validate_transit_relay_url(relay_url)?;
+        }
+        let max_bytes_per_second = config.max_bytes_per_second.map(Self::validate_bandwidth_limit).transpose()?;
+
+        Ok(Self {
+            max_bytes_per_second: max_bytes_per_second.flatten(),
+            ..config
+        })
+    }
+
+    #[wasm_bindgen]
+    /// Serializes the configuration to JSON, e.g. to persist it in `localStorage`.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("the configuration only contains JSON-compatible values")
+    }
+
+    /// Checks that the app ID is non-empty, and looks like the `<domain>/<path>` IDs that the server expects.
+    fn validate_id(id: &str) -> Result<(), WormholeError> {
+        let invalid = |reason: &str| WormholeError::AppId {
+            id: id.into(),
+            reason: reason.into(),
+        };
+
+        if id.is_empty() {
+            Err(invalid("must not be empty"))
+        } else if id.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            Err(invalid("must not contain whitespace or control characters"))
+        } else if !id.contains('/') {
+            Err(invalid("expected a URI-like ID, e.g. `example.com/my-app`"))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checks that the rendezvous URL parses, and uses a websocket scheme.
+    pub(crate) fn validate_rendezvous_url(rendezvous_url: &str) -> Result<(), WormholeError> {
+        let invalid = |reason: String| WormholeError::RendezvousUrl {
+            url: rendezvous_url.into(),
+            reason,
+        };
+        let url = url::Url::parse(rendezvous_url).map_err(|e| invalid(e.to_string()))?;
+
+        match url.scheme() {
+            "ws" | "wss" => Ok(()),
+            scheme => Err(invalid(format!("expected a `ws` or `wss` scheme, got `{scheme}`"))),
+        }
+    }
+
+    /// Checks that the bandwidth limit is a non-negative number, and returns it, or `None` if it is `0` (no limit).
+    fn validate_bandwidth_limit(limit: f64) -> Result<Option<f64>, WormholeError> {
+        if !limit.is_finite() || limit < 0.0 {
+            return Err(WormholeError::InvalidConfig(format!("invalid bandwidth limit `{limit}`: expected a non-negative number")));
+        }
+
+        Ok(Some(limit).filter(|&limit| limit > 0.0))
+    }
+
+    /// Keeps the valid relay URLs of a relay list fetched from `source`, logging a warning for each skipped entry.
+    fn valid_relay_urls(entries: Vec<serde_json::Value>, source: &str) -> Vec<String> {
+        entries
+            .into_iter()
+            .filter_map(|entry| match entry {
+                serde_json::Value::String(relay_url) if Self::validate_transit_relay_url(&relay_url).is_ok() => Some(relay_url),
+                entry => {
+                    log::warn!("skipping invalid transit relay `{entry}` from `{source}`");
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Checks that the transit relay URL parses, and uses a scheme that the transit layer supports.
+    fn validate_transit_relay_url(relay_url: &str) -> Result<url::Url, WormholeError> {
+        let invalid = |reason: String| WormholeError::TransitRelayUrl {
+            url: relay_url.into(),
+            reason,
+        };
+        let url = url::Url::parse(relay_url).map_err(|e| invalid(e.to_string()))?;
+
+        match url.scheme() {
+            "tcp" | "ws" | "wss" => Ok(url),
+            scheme => Err(invalid(format!("expected a `tcp`, `ws` or `wss` scheme, got `{scheme}`"))),
+        }
+    }
+
+    /// Generates the relay hints for the configured transit relays, falling back to `default_transit_relay_url`.
+    ///
+    /// The WASM build can only reach websocket relays, so it fails early if only TCP relays are configured, rather
+    /// than offering relays that the transfer can't use.
+    pub(crate) fn relay_hints(&self) -> Result<Vec<RelayHint>, WormholeError> {
+        let relay_urls = if self.transit_relay_urls.is_empty() {
+            vec![default_transit_relay_url().parse()?]
+        } else {
+            self.transit_relay_urls
+                .iter()
+                .map(|relay_url| Self::validate_transit_relay_url(relay_url))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+
+        if cfg!(target_arch = "wasm32") && relay_urls.iter().all(|url| url.scheme() == "tcp") {
+            return Err(WormholeError::InvalidConfig(
+                "browsers can only reach websocket transit relays: configure a `ws://` or `wss://` relay with `set_transit_relay_url`".into(),
+            ));
+        }
+
+        relay_urls
+            .into_iter()
+            .map(|url| Ok(RelayHint::from_urls(None, [url])?))
+            .collect()
+    }
+
+    /// Generates the transit abilities for the configured transit mode.
+    pub(crate) fn transit_abilities(&self) -> Abilities {
+        self.transit_mode.into()
+    }
+
+    /// Replaces the app version, keeping the fields that the other app version setters manage.
+    fn replace_app_version(&mut self, mut app_version: serde_json::Value) -> Result<(), WormholeError> {
+        let Some(fields) = app_version.as_object_mut() else {
+            return Err(WormholeError::AppVersion(format!("expected a JSON object, got `{app_version}`")));
+        };
+        for key in [ABILITIES_KEY, DEVICE_NAME_KEY, PROTOCOL_VERSION_KEY] {
+            if let Some(value) = self.app_version.get(key) {
+                fields.entry(key).or_insert_with(|| value.clone());
+            }
+        }
+        serde_json::from_value::<AppVersion>(app_version.clone())
+            .map_err(|e| WormholeError::AppVersion(e.to_string()))?;
+
+        self.app_version = app_version;
+        Ok(())
+    }
+
+    /// Generates the app version that the standard transfer protocol advertises, along with the protocol version of the bindings.
+    fn default_app_version() -> serde_json::Value {
+        let mut app_version = serde_json::to_value(AppVersion {}).unwrap_or_else(|_| serde_json::json!({}));
+        if let Some(fields) = app_version.as_object_mut() {
+            fields.insert(PROTOCOL_VERSION_KEY.into(), PROTOCOL_VERSION.into());
+        }
+
+        app_version
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_id(&mut self, id: String) -> Result<(), WormholeError> {
+        Self::validate_id(&id)?;
+
+        self.id = id;
+        Ok(())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn rendezvous_url(&self) -> String {
+        self.rendezvous_url.clone()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_rendezvous_url(&mut self, rendezvous_url: String) -> Result<(), WormholeError> {
+        Self::validate_rendezvous_url(&rendezvous_url)?;
+
+        self.rendezvous_url = rendezvous_url;
+        Ok(())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn app_version(&self) -> JsValue {
+        json_to_js(&self.app_version)
+    }
+
+    #[wasm_bindgen(setter)]
+    /// Sets the app version that is advertised to the peer during the handshake.
+    ///
+    /// The flags from `set_app_version_flags`, the name from `set_device_name` and the protocol version (see
+    /// `set_strict_version_check`) are kept, unless the new app version sets them itself, so the setters can be called
+    /// in any order.
+    ///
+    /// # Arguments
+    ///
+    /// * `app_version` - A JSON object, compatible with the app version of the transfer protocol.
+    pub fn set_app_version(&mut self, app_version: JsValue) -> Result<(), WormholeError> {
+        let app_version: serde_json::Value = serde_wasm_bindgen::from_value(app_version)
+            .map_err(|e| WormholeError::AppVersion(e.to_string()))?;
+
+        self.replace_app_version(app_version)
+    }
+
+    #[wasm_bindgen]
+    /// Sets capability flags (e.g. `{ "supports_resume": true }`) that are advertised to the peer as part of the app version.
+    ///
+    /// The peer can read them with `Wormhole.peer_abilities`. Peers that don't know about the flags ignore them.
+    ///
+    /// # Arguments
+    ///
+    /// * `flags` - A JSON object with the flags.
+    pub fn set_app_version_flags(&mut self, flags: JsValue) -> Result<(), WormholeError> {
+        let flags: serde_json::Value =
+            serde_wasm_bindgen::from_value(flags).map_err(|e| WormholeError::AppVersion(e.to_string()))?;
+
+        if !flags.is_object() {
+            return Err(WormholeError::AppVersion(format!("expected the flags to be a JSON object, got `{flags}`")));
+        }
+        if let Some(app_version) = self.app_version.as_object_mut() {
+            app_version.insert(ABILITIES_KEY.into(), flags);
+        }
+
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    /// Sets a human-readable name of this device (e.g. `Alice's laptop`), which is advertised to the peer as part of the app version.
+    ///
+    /// The peer can read it with `Wormhole.peer_device_name`. Control characters are removed, and the name is truncated
+    /// to 64 characters. An empty name removes it.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the device.
+    pub fn set_device_name(&mut self, name: String) {
+        let Some(app_version) = self.app_version.as_object_mut() else {
+            return;
+        };
+
+        let name = sanitize_device_name(&name);
+        if name.is_empty() {
+            app_version.remove(DEVICE_NAME_KEY);
+        } else {
+            app_version.insert(DEVICE_NAME_KEY.into(), name.into());
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn transit_relay_urls(&self) -> Vec<String> {
+        self.transit_relay_urls.clone()
+    }
+
+    #[wasm_bindgen]
+    /// Sets the transit relay to use for transfers, replacing any previously configured relays.
+    ///
+    /// If no relay is configured, the one from `default_transit_relay_url` is used. Browsers can only reach websocket
+    /// (`ws://` or `wss://`) relays, so in the WASM build transfers fail with a `Config` error if only `tcp://` relays
+    /// are configured.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the relay, e.g. `tcp://transit.magic-wormhole.io:4001`. Must use the `tcp://`, `ws://` or `wss://` scheme.
+    pub fn set_transit_relay_url(&mut self, url: String) -> Result<(), WormholeError> {
+        Self::validate_transit_relay_url(&url)?;
+
+        self.transit_relay_urls = vec![url];
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    /// Adds a transit relay to use for transfers, in addition to the previously configured relays.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the relay. Must use the `tcp://`, `ws://` or `wss://` scheme.
+    pub fn add_transit_relay_url(&mut self, url: String) -> Result<(), WormholeError> {
+        Self::validate_transit_relay_url(&url)?;
+
+        self.transit_relay_urls.push(url);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    /// Sets the transit relays to use for transfers, replacing any previously configured relays.
+    ///
+    /// The configuration is left unchanged if any of the URLs is invalid.
+    ///
+    /// # Arguments
+    ///
+    /// * `urls` - The URLs of the relays, e.g. from `load_relay_hints_from_url`. Must use the `tcp://`, `ws://` or `wss://` scheme.
+    pub fn set_transit_relay_urls(&mut self, urls: Vec<String>) -> Result<(), WormholeError> {
+        for url in &urls {
+            Self::validate_transit_relay_url(url)?;
+        }
+
+        self.transit_relay_urls = urls;
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    /// Fetches a JSON array of transit relay URLs from the given endpoint, to be used with `set_transit_relay_urls`.
+    ///
+    /// Malformed entries are skipped with a logged warning. The load only fails if the endpoint can't be fetched,
+    /// doesn't return a JSON array, or doesn't list any valid relay.
+    ///
+    /// This doesn't touch any configuration, so configurations can still be changed (or used) while the request is pending.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the endpoint, e.g. `https://example.com/relays.json`.
+    pub async fn load_relay_hints_from_url(url: String) -> Result<Vec<String>, WormholeError> {
+        let failed = |reason: String| WormholeError::RelayDiscovery { url: url.clone(), reason };
+
+        let response: Response = JsFuture::from(fetch(&url))
+            .await
+            .and_then(|response| response.dyn_into())
+            .map_err(|e| failed(format!("{e:?}")))?;
+        if !response.ok() {
+            return Err(failed(format!("the server responded with status {}", response.status())));
+        }
+        let json = JsFuture::from(response.json().map_err(|e| failed(format!("{e:?}")))?)
+            .await
+            .map_err(|e| failed(format!("invalid JSON: {e:?}")))?;
+        let entries: Vec<serde_json::Value> =
+            serde_wasm_bindgen::from_value(json).map_err(|e| failed(format!("expected an array of relay URLs: {e}")))?;
+
+        let relay_urls = Self::valid_relay_urls(entries, &url);
+        if relay_urls.is_empty() {
+            return Err(failed("no valid relay URL was listed".into()));
+        }
+
+        Ok(relay_urls)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn transit_mode(&self) -> TransitMode {
+        self.transit_mode
+    }
+
+    #[wasm_bindgen(setter)]
+    /// Sets the transit connections that transfers are allowed to use. Defaults to `TransitMode.All`.
+    ///
+    /// `TransitMode.RelayOnly` avoids revealing the local IP addresses of either side to the peer,
+    /// at the cost of routing all data through the relay server (which can read the encrypted stream, but not decrypt it).
+    /// `TransitMode.DirectOnly` avoids relays altogether, but fails if the peers can't reach each other directly.
+    pub fn set_transit_mode(&mut self, transit_mode: TransitMode) {
+        self.transit_mode = transit_mode;
+    }
+
+    #[wasm_bindgen(getter)]
+    /// The bandwidth limit for transfers, in bytes per second, or `0` if there is none.
+    pub fn max_bytes_per_second(&self) -> f64 {
+        self.max_bytes_per_second.unwrap_or(0.0)
+    }
+
+    #[wasm_bindgen(setter)]
+    /// Limits the bandwidth of transfers, when sending (files, streams and folders) as well as receiving.
+    ///
+    /// Bursts of up to one second worth of bytes are let through, so that the progress doesn't stall.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The limit, in bytes per second. `0` removes the limit.
+    pub fn set_max_bytes_per_second(&mut self, limit: f64) -> Result<(), WormholeError> {
+        self.max_bytes_per_second = Self::validate_bandwidth_limit(limit)?;
+
+        Ok(())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn require_verification(&self) -> bool {
+        self.require_verification
+    }
+
+    #[wasm_bindgen(setter)]
+    /// Sets whether transfers wait for the user to confirm the verifier before starting. Defaults to `false`.
+    ///
+    /// When enabled, every transfer first invokes the verification callback with a `VerificationRequest`, and only
+    /// proceeds once `confirm_verification(true)` is called on it. Rejecting it aborts with a `VerificationRejected` error.
+    pub fn set_require_verification(&mut self, require_verification: bool) {
+        self.require_verification = require_verification;
+    }
+
+    #[wasm_bindgen]
+    /// Sets the callback that is invoked with a `VerificationRequest` before each transfer, if verification is required.
+    ///
+    /// It isn't part of the serialized configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - The callback, or `undefined` to remove it.
+    pub fn set_verification_callback(&mut self, callback: Option<Function>) {
+        self.verification_callback = callback;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn strict_version_check(&self) -> bool {
+        self.strict_version_check
+    }
+
+    #[wasm_bindgen(setter)]
+    /// Sets whether connecting to a peer with an incompatible protocol version fails. Defaults to `false`.
+    ///
+    /// Once the handshake completed, the `protocol_version` of both app versions is compared. It defaults to the
+    /// version of the protocol extensions of these bindings, and apps can advertise their own with `set_app_version`.
+    /// Peers that don't advertise one (e.g. other transfer clients) are assumed to be compatible.
+    ///
+    /// If the versions differ, connecting fails with an `IncompatiblePeer` error in strict mode. Otherwise, a warning
+    /// is logged, the version warning callback is invoked, and the wormhole is established anyway, to preserve
+    /// interoperability.
+    pub fn set_strict_version_check(&mut self, strict_version_check: bool) {
+        self.strict_version_check = strict_version_check;
+    }
+
+    #[wasm_bindgen]
+    /// Sets a callback that is invoked with a `{ local, peer }` object of the protocol versions, when connecting to a
+    /// peer with an incompatible protocol version in lenient mode, see `set_strict_version_check`.
+    ///
+    /// It isn't part of the serialized configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - The callback, or `undefined` to remove it.
+    pub fn set_version_warning_callback(&mut self, callback: Option<Function>) {
+        self.version_warning_callback = callback;
+    }
+
+    #[wasm_bindgen]
+    /// Sets a callback that is invoked with the new status whenever a connection or transfer made with this
+    /// configuration changes state.
+    ///
+    /// The status is one of `"Connecting"`, `"WaitingForPeer"`, `"PeerConnected"`, `"Transferring"`, `"Done"` or
+    /// `"Failed"`. The callback runs on the event loop, in between the steps of the operation, so it must not block.
+    /// It isn't part of the serialized configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - The callback, or `undefined` to remove it.
+    pub fn set_status_callback(&mut self, callback: Option<Function>) {
+        self.status_callback = callback;
+    }
+
+    #[wasm_bindgen]
+    /// Sets a callback that is invoked with the server's message of the day, as soon as the server sent it.
+    ///
+    /// It fires once per connection made with this configuration, and only if the server sent a message.
+    /// When connecting without a code, this happens before waiting for the peer. When connecting with a code,
+    /// the message is only known once the handshake completed. It isn't part of the serialized configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - The callback, or `undefined` to remove it.
+    pub fn set_motd_callback(&mut self, callback: Option<Function>) {
+        self.motd_callback = callback;
+    }
+
+    #[wasm_bindgen]
+    /// Sets a callback that is invoked with a `TransitInfo` once the transit connection of a transfer made with this
+    /// configuration is established.
+    ///
+    /// This tells whether the data goes through a relay, e.g. to explain slow transfers. It isn't part of the
+    /// serialized configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - The callback, or `undefined` to remove it.
+    pub fn set_transit_callback(&mut self, callback: Option<Function>) {
+        self.transit_callback = callback;
+    }
+
+    #[wasm_bindgen]
+    /// Sets a callback that is invoked with a `{ event, timestamp, detail }` object at each milestone of a connection
+    /// or transfer made with this configuration, e.g. for analytics.
+    ///
+    /// Unlike the status callback, this is an append-only log with details. The events are `"connecting"`,
+    /// `"code_generated"` (with the `nameplate` and the `code_length`, but never the words), `"waiting_for_peer"`,
+    /// `"peer_connected"`, `"transfer_started"`, `"transfer_finished"`, `"bytes_transferred"` (with the `bytes` and the
+    /// `duration_ms`) and `"failed"` (with the error `kind` and `message`). The `timestamp` is as per `Date.now()`.
+    /// Exceptions thrown by the callback are ignored. It isn't part of the serialized configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - The callback, or `undefined` to remove it.
+    pub fn set_event_callback(&mut self, callback: Option<Function>) {
+        self.event_callback = callback;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn json_with(key: &str, value: serde_json::Value) -> String {
+        let mut json: serde_json::Value = serde_json::from_str(&AppConfig::new_unchecked(default_app_id(), default_rendezvous_url()).to_json()).unwrap();
+        json[key] = value;
+
+        json.to_string()
+    }
+
+    #[test]
+    fn from_json_removes_a_zero_bandwidth_limit() {
+        let config = AppConfig::from_json(&json_with("max_bytes_per_second", 0.into())).unwrap();
+
+        assert_eq!(config.max_bytes_per_second, None);
+    }
+
+    #[test]
+    fn from_json_keeps_a_positive_bandwidth_limit() {
+        let config = AppConfig::from_json(&json_with("max_bytes_per_second", 1024.into())).unwrap();
+
+        assert_eq!(config.max_bytes_per_second, Some(1024.0));
+    }
+
+    #[test]
+    fn from_json_rejects_a_negative_bandwidth_limit() {
+        let result = AppConfig::from_json(&json_with("max_bytes_per_second", (-1).into()));
+
+        assert!(matches!(result, Err(WormholeError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn namespaces_are_appended_to_the_base_id() {
+        let config = AppConfig::with_namespace("example.com/my-app/", "staging").unwrap();
+
+        assert_eq!(config.id(), "example.com/my-app/staging");
+    }
+
+    #[test]
+    fn different_namespaces_produce_different_ids() {
+        let staging = AppConfig::with_namespace("example.com/my-app", "staging").unwrap();
+        let production = AppConfig::with_namespace("example.com/my-app", "production").unwrap();
+
+        assert_ne!(staging.id(), production.id());
+    }
+
+    #[test]
+    fn namespaces_must_be_single_segments() {
+        for namespace in ["", "a/b", "/"] {
+            let result = AppConfig::with_namespace("example.com/my-app", namespace);
+            assert!(matches!(result, Err(WormholeError::AppId { .. })), "{namespace}");
+        }
+    }
+
+    #[test]
+    fn transit_modes_select_the_abilities() {
+        let direct_only = Abilities::from(TransitMode::DirectOnly);
+        assert!(direct_only.can_direct() && !direct_only.can_relay());
+
+        let relay_only = Abilities::from(TransitMode::RelayOnly);
+        assert!(!relay_only.can_direct() && relay_only.can_relay());
+
+        let all = Abilities::from(TransitMode::All);
+        assert!(all.can_direct() && all.can_relay());
+    }
+
+    #[test]
+    fn transit_mode_defaults_to_all() {
+        let config = AppConfig::new_unchecked(default_app_id(), default_rendezvous_url());
+
+        assert_eq!(config.transit_mode(), TransitMode::All);
+    }
+
+    #[test]
+    fn relay_lists_keep_only_valid_urls() {
+        let entries = vec![
+            "wss://relay.example.com:443".into(),
+            "https://relay.example.com".into(),
+            42.into(),
+            "tcp://relay.example.com:4001".into(),
+        ];
+
+        assert_eq!(
+            AppConfig::valid_relay_urls(entries, "https://example.com/relays.json"),
+            ["wss://relay.example.com:443", "tcp://relay.example.com:4001"]
+        );
+    }
+
+    #[test]
+    fn setting_relay_urls_is_all_or_nothing() {
+        let mut config = AppConfig::new_unchecked(default_app_id(), default_rendezvous_url());
+        config.set_transit_relay_urls(vec!["wss://relay.example.com".into()]).unwrap();
+
+        let result = config.set_transit_relay_urls(vec!["wss://other.example.com".into(), "not a url".into()]);
+        assert!(result.is_err());
+        assert_eq!(config.transit_relay_urls(), ["wss://relay.example.com"]);
+    }
+
+    #[test]
+    fn replacing_the_app_version_keeps_the_flags_and_the_device_name() {
+        let mut config = AppConfig::new_unchecked(default_app_id(), default_rendezvous_url());
+        config.app_version[ABILITIES_KEY] = serde_json::json!({ "supports_resume": true });
+        config.set_device_name("laptop".into());
+
+        config.replace_app_version(serde_json::json!({ "custom": 1 })).unwrap();
+        assert_eq!(
+            config.app_version,
+            serde_json::json!({
+                "custom": 1,
+                ABILITIES_KEY: { "supports_resume": true },
+                DEVICE_NAME_KEY: "laptop",
+                PROTOCOL_VERSION_KEY: PROTOCOL_VERSION,
+            })
+        );
+    }
+
+    #[test]
+    fn replacing_the_app_version_can_override_the_device_name() {
+        let mut config = AppConfig::new_unchecked(default_app_id(), default_rendezvous_url());
+        config.set_device_name("laptop".into());
+
+        config.replace_app_version(serde_json::json!({ DEVICE_NAME_KEY: "phone" })).unwrap();
+        assert_eq!(config.app_version[DEVICE_NAME_KEY], "phone");
+    }
+
+    #[test]
+    fn unconfigured_relays_fall_back_to_the_default_relay() {
+        let config = AppConfig::new_default();
+
+        assert_eq!(default_transit_relay_url(), DEFAULT_RELAY_SERVER);
+        assert_eq!(config.relay_hints().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn app_versions_must_be_objects() {
+        let mut config = AppConfig::new_unchecked(default_app_id(), default_rendezvous_url());
+
+        assert!(matches!(config.replace_app_version(serde_json::json!([])), Err(WormholeError::AppVersion(_))));
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use js_sys::{Function, Reflect};
+    use wasm_bindgen::JsValue;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::{default_transit_relay_url, AppConfig, WormholeError};
+
+    /// Replaces the global `fetch` with one that responds with the given body and status.
+    fn mock_fetch(body: &str, status: u16) {
+        let body = serde_json::to_string(body).unwrap();
+        let fetch = Function::new_with_args("url", &format!("return Promise.resolve(new Response({body}, {{ status: {status} }}))"));
+        Reflect::set(&js_sys::global(), &JsValue::from_str("fetch"), &fetch).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn the_default_config_falls_back_to_a_websocket_relay() {
+        assert!(default_transit_relay_url().starts_with("wss://"));
+        assert_eq!(AppConfig::new_default().relay_hints().unwrap().len(), 1);
+    }
+
+    #[wasm_bindgen_test]
+    async fn relay_hints_are_loaded_from_the_endpoint() {
+        mock_fetch(r#"["wss://relay.example.com", "not a url"]"#, 200);
+
+        let relay_urls = AppConfig::load_relay_hints_from_url("https://example.com/relays.json".into()).await.unwrap();
+        assert_eq!(relay_urls, ["wss://relay.example.com"]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn failed_responses_are_discovery_errors() {
+        mock_fetch("", 404);
+
+        let result = AppConfig::load_relay_hints_from_url("https://example.com/relays.json".into()).await;
+        assert!(matches!(result, Err(WormholeError::RelayDiscovery { .. })));
+    }
+
+    #[wasm_bindgen_test]
+    async fn lists_without_valid_relays_are_discovery_errors() {
+        mock_fetch("[42]", 200);
+
+        let result = AppConfig::load_relay_hints_from_url("https://example.com/relays.json".into()).await;
+        assert!(matches!(result, Err(WormholeError::RelayDiscovery { .. })));
+    }
+}
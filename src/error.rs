@@ -0,0 +1,317 @@
+use magic_wormhole::transfer::TransferError;
+use magic_wormhole::transit::RelayHintParseError;
+use magic_wormhole::WormholeError as WhError;
+use thiserror::Error;
+use wasm_bindgen::prelude::*;
+
+use crate::code::{MAX_CODE_LENGTH, MIN_CODE_LENGTH};
+
+#[derive(Error, Debug)]
+pub enum WormholeError {
+    #[error(transparent)]
+    Wormhole(WhError),
+    #[error("The code didn't match — check for typos and try again.")]
+    WrongCode,
+    #[error("The verifier was rejected")]
+    VerificationRejected,
+    #[error("The wormhole was already taken")]
+    WormholeTaken,
+    #[error(transparent)]
+    Transfer(TransferError),
+    #[error("Invalid relay hint: {0}")]
+    RelayHint(#[from] RelayHintParseError),
+    #[error("Invalid URL: {0}")]
+    Url(#[from] url::ParseError),
+    #[error("The operation was cancelled")]
+    Cancelled,
+    #[error("The peer cancelled the transfer")]
+    CancelledByPeer,
+    #[error("The operation timed out")]
+    Timeout,
+    #[error("Invalid app version: {0}")]
+    AppVersion(String),
+    #[error("Invalid app ID `{id}`: {reason}")]
+    AppId { id: String, reason: String },
+    #[error("Invalid rendezvous URL `{url}`: {reason}")]
+    RendezvousUrl { url: String, reason: String },
+    #[error("Invalid transit relay URL `{url}`: {reason}")]
+    TransitRelayUrl { url: String, reason: String },
+    #[error("Failed to load the transit relays from `{url}`: {reason}")]
+    RelayDiscovery { url: String, reason: String },
+    #[error("Invalid code length {0}: must be between {min} and {max}", min = MIN_CODE_LENGTH, max = MAX_CODE_LENGTH)]
+    InvalidCodeLength(usize),
+    #[error("Invalid code `{code}`: {reason}")]
+    InvalidCode { code: String, reason: String },
+    #[error("Invalid transfer URI `{uri}`: {reason}")]
+    InvalidUri { uri: String, reason: String },
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+    #[error("Invalid folder entry `{path}`: {reason}")]
+    InvalidFolderEntry { path: String, reason: String },
+    #[error("The offered file is {size} bytes, which exceeds the limit of {max} bytes")]
+    FileTooLarge { size: u64, max: u64 },
+    #[error("The peer offered {expected} bytes, but sent {actual}")]
+    SizeMismatch { expected: u64, actual: u64 },
+    #[error("The framed message is {size} bytes, which exceeds the limit of {max} bytes")]
+    FrameTooLarge { size: u64, max: u64 },
+    #[error("Invalid framed message: {0}")]
+    InvalidFrame(String),
+    #[error("Invalid JSON message: {0}")]
+    InvalidMessage(String),
+    #[error("The peer speaks protocol version {peer}, which is incompatible with version {local}")]
+    IncompatiblePeer { local: String, peer: String },
+}
+
+impl From<WhError> for WormholeError {
+    fn from(value: WhError) -> Self {
+        match value {
+            // The key confirmation only fails if the peers derived different keys, i.e. entered different codes.
+            WhError::PakeFailed => Self::WrongCode,
+            e => Self::Wormhole(e),
+        }
+    }
+}
+
+/// The message that the transfer functions send to the peer when they are cancelled, i.e. the `Display` of the
+/// cancellation error of the wormhole library.
+const PEER_CANCELLED_MESSAGE: &str = "Task has been cancelled";
+
+impl From<TransferError> for WormholeError {
+    fn from(value: TransferError) -> Self {
+        match value {
+            TransferError::PeerError(message) if message == PEER_CANCELLED_MESSAGE => Self::CancelledByPeer,
+            e => Self::Transfer(e),
+        }
+    }
+}
+
+impl WormholeError {
+    /// Classifies the error into a kind that JS code can match on.
+    pub fn kind(&self) -> WormholeErrorKind {
+        match self {
+            Self::Wormhole(e) => Self::wormhole_kind(e),
+            Self::Transfer(TransferError::Wormhole(e)) => Self::wormhole_kind(e),
+            Self::Transfer(TransferError::IO(_)) => WormholeErrorKind::Io,
+            Self::Transfer(TransferError::PeerError(_)) => WormholeErrorKind::PeerError,
+            Self::Transfer(_) => WormholeErrorKind::Transfer,
+            Self::RelayDiscovery { .. } => WormholeErrorKind::Io,
+            Self::RelayHint(_)
+            | Self::Url(_)
+            | Self::AppVersion(_)
+            | Self::AppId { .. }
+            | Self::RendezvousUrl { .. }
+            | Self::TransitRelayUrl { .. }
+            | Self::InvalidCodeLength(_)
+            | Self::InvalidConfig(_)
+            | Self::WormholeTaken
+            | Self::InvalidFolderEntry { .. } => WormholeErrorKind::Config,
+            Self::InvalidCode { .. } | Self::InvalidUri { .. } => WormholeErrorKind::InvalidCode,
+            Self::FileTooLarge { .. } => WormholeErrorKind::FileTooLarge,
+            Self::SizeMismatch { .. } | Self::FrameTooLarge { .. } | Self::InvalidFrame(_) | Self::InvalidMessage(_) => WormholeErrorKind::Protocol,
+            Self::WrongCode => WormholeErrorKind::WrongCode,
+            Self::VerificationRejected => WormholeErrorKind::VerificationRejected,
+            Self::Cancelled => WormholeErrorKind::Cancelled,
+            Self::CancelledByPeer => WormholeErrorKind::CancelledByPeer,
+            Self::Timeout => WormholeErrorKind::Timeout,
+            Self::IncompatiblePeer { .. } => WormholeErrorKind::IncompatiblePeer,
+        }
+    }
+
+    /// Whether the error is transient, i.e. retrying the operation might succeed.
+    ///
+    /// This is the case for server and network errors, and for timeouts. Errors that would just happen again
+    /// (e.g. `WrongCode`, rejected offers or protocol violations) are not retryable.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::Wormhole(WhError::ServerError(_)) | Self::Transfer(TransferError::Wormhole(WhError::ServerError(_))) | Self::Timeout
+        )
+    }
+
+    /// How long to wait before retrying, in milliseconds, if the server supplied backoff guidance.
+    ///
+    /// The rendezvous protocol has no such guidance (the server's errors are plain messages), so this is `None` for
+    /// now, and retries fall back to the backoff of their `ReconnectPolicy`.
+    pub fn retry_after_ms(&self) -> Option<f64> {
+        None
+    }
+
+    fn wormhole_kind(error: &WhError) -> WormholeErrorKind {
+        match error {
+            WhError::PakeFailed => WormholeErrorKind::WrongCode,
+            WhError::ServerError(_) => WormholeErrorKind::ServerError,
+            WhError::Crypto => WormholeErrorKind::Crypto,
+            _ => WormholeErrorKind::Protocol,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// The kind of a `WormholeError`.
+pub enum WormholeErrorKind {
+    /// The key confirmation failed, because the two peers entered different codes.
+    WrongCode,
+    /// An error with the rendezvous server connection.
+    ServerError,
+    /// A received message could not be decrypted.
+    Crypto,
+    /// The peer or the server violated the protocol.
+    Protocol,
+    /// An I/O error occurred during the transfer.
+    Io,
+    /// The file transfer failed.
+    Transfer,
+    /// The peer aborted the transfer, e.g. because it failed to write the file on its side.
+    PeerError,
+    /// The configuration is invalid.
+    Config,
+    /// The user rejected the verifier, see `AppConfig.require_verification`.
+    VerificationRejected,
+    /// The wormhole code (or the transfer URI containing it) is malformed.
+    InvalidCode,
+    /// The offered file exceeds the configured size limit.
+    FileTooLarge,
+    /// The operation was cancelled.
+    Cancelled,
+    /// The operation timed out.
+    Timeout,
+    /// The peer advertised an incompatible protocol version, see `AppConfig.strict_version_check`.
+    IncompatiblePeer,
+    /// The peer cancelled the transfer.
+    CancelledByPeer,
+}
+
+#[wasm_bindgen(js_name = WormholeError)]
+/// The error that the rejected promises of this module carry.
+pub struct JsWormholeError {
+    kind: WormholeErrorKind,
+    message: String,
+    retryable: bool,
+    retry_after_ms: Option<f64>,
+}
+
+#[wasm_bindgen(js_class = WormholeError)]
+impl JsWormholeError {
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> WormholeErrorKind {
+        self.kind
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    /// Whether retrying the operation might succeed, e.g. after a server or network error.
+    pub fn is_retryable(&self) -> bool {
+        self.retryable
+    }
+
+    #[wasm_bindgen(getter)]
+    /// How long to wait before retrying, in milliseconds, if the server supplied backoff guidance.
+    pub fn retry_after_ms(&self) -> Option<f64> {
+        self.retry_after_ms
+    }
+
+    #[wasm_bindgen(js_name = toString)]
+    pub fn js_to_string(&self) -> String {
+        self.message.clone()
+    }
+}
+
+impl From<WormholeError> for JsWormholeError {
+    fn from(value: WormholeError) -> Self {
+        Self {
+            kind: value.kind(),
+            message: value.to_string(),
+            retryable: value.is_retryable(),
+            retry_after_ms: value.retry_after_ms(),
+        }
+    }
+}
+
+impl From<WormholeError> for JsValue {
+    fn from(value: WormholeError) -> Self {
+        JsWormholeError::from(value).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pake_failures_are_wrong_codes() {
+        let error = WormholeError::from(WhError::PakeFailed);
+
+        assert!(matches!(error, WormholeError::WrongCode));
+        assert_eq!(error.kind(), WormholeErrorKind::WrongCode);
+        assert!(!error.is_retryable());
+        assert_eq!(error.to_string(), "The code didn't match — check for typos and try again.");
+    }
+
+    #[test]
+    fn peer_cancellations_are_distinct_from_peer_errors() {
+        let error = WormholeError::from(TransferError::PeerError(PEER_CANCELLED_MESSAGE.into()));
+        assert!(matches!(error, WormholeError::CancelledByPeer));
+        assert_eq!(error.kind(), WormholeErrorKind::CancelledByPeer);
+
+        let error = WormholeError::from(TransferError::PeerError("failed to write the file".into()));
+        assert_eq!(error.kind(), WormholeErrorKind::PeerError);
+    }
+
+    #[test]
+    fn errors_are_classified_by_kind() {
+        let cases = [
+            (WormholeError::Wormhole(WhError::Crypto), WormholeErrorKind::Crypto),
+            (WormholeError::Wormhole(WhError::Protocol("unexpected message".into())), WormholeErrorKind::Protocol),
+            (WormholeError::Transfer(TransferError::Wormhole(WhError::Crypto)), WormholeErrorKind::Crypto),
+            (
+                WormholeError::Transfer(TransferError::IO(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "closed"))),
+                WormholeErrorKind::Io,
+            ),
+            (WormholeError::Transfer(TransferError::PeerError("aborted".into())), WormholeErrorKind::PeerError),
+            (WormholeError::VerificationRejected, WormholeErrorKind::VerificationRejected),
+            (WormholeError::InvalidCodeLength(0), WormholeErrorKind::Config),
+            (WormholeError::InvalidUri { uri: String::new(), reason: String::new() }, WormholeErrorKind::InvalidCode),
+            (WormholeError::FileTooLarge { size: 2, max: 1 }, WormholeErrorKind::FileTooLarge),
+            (WormholeError::SizeMismatch { expected: 2, actual: 1 }, WormholeErrorKind::Protocol),
+            (WormholeError::Cancelled, WormholeErrorKind::Cancelled),
+            (WormholeError::CancelledByPeer, WormholeErrorKind::CancelledByPeer),
+            (WormholeError::Timeout, WormholeErrorKind::Timeout),
+        ];
+
+        for (error, kind) in cases {
+            assert_eq!(error.kind(), kind, "{error}");
+        }
+    }
+
+    #[test]
+    fn only_transient_errors_are_retryable() {
+        assert!(WormholeError::Timeout.is_retryable());
+        assert_eq!(WormholeError::Timeout.retry_after_ms(), None);
+
+        for error in [
+            WormholeError::Wormhole(WhError::Crypto),
+            WormholeError::Wormhole(WhError::Protocol("unexpected message".into())),
+            WormholeError::Transfer(TransferError::PeerError("aborted".into())),
+            WormholeError::VerificationRejected,
+            WormholeError::Cancelled,
+        ] {
+            assert!(!error.is_retryable(), "{error}");
+            assert_eq!(error.retry_after_ms(), None, "{error}");
+        }
+    }
+
+    #[test]
+    fn js_errors_carry_the_classification() {
+        let error = JsWormholeError::from(WormholeError::Timeout);
+
+        assert_eq!(error.kind(), WormholeErrorKind::Timeout);
+        assert!(error.is_retryable());
+        assert_eq!(error.retry_after_ms(), None);
+        assert_eq!(error.message(), "The operation timed out");
+    }
+}
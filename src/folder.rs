@@ -0,0 +1,229 @@
+use std::collections::btree_map::{BTreeMap, Entry};
+use std::io;
+use std::sync::Arc;
+
+use futures::future;
+use futures::io::{AsyncRead, Cursor};
+use js_sys::{Date, Function};
+use magic_wormhole::transfer::{self, OfferSend, OfferSendEntry};
+use serde::Deserialize;
+use serde_bytes::ByteBuf;
+use wasm_bindgen::prelude::*;
+
+use crate::cancel::cancellable_transfer;
+use crate::session::Session;
+use crate::status::{track, WormholeStatus};
+use crate::throttle::Throttle;
+use crate::transfer::{progress_handler, TransferStats};
+use crate::{CancelToken, Wormhole, WormholeError};
+
+#[derive(Deserialize)]
+/// A file inside a folder, as passed from JS.
+struct FolderEntry {
+    /// The path of the file, relative to the root of the folder, using `/` as the separator.
+    path: String,
+    bytes: ByteBuf,
+}
+
+/// Splits a relative path into its components, rejecting paths that could escape the root of the folder.
+fn path_components(path: &str) -> Result<Vec<&str>, WormholeError> {
+    let invalid = |reason: &str| WormholeError::InvalidFolderEntry {
+        path: path.into(),
+        reason: reason.into(),
+    };
+
+    if path.starts_with('/') || path.contains('\\') {
+        return Err(invalid("expected a relative path, using `/` as the separator"));
+    }
+    let components: Vec<&str> = path.split('/').filter(|component| !component.is_empty() && *component != ".").collect();
+    if components.is_empty() {
+        return Err(invalid("the path must not be empty"));
+    }
+    if components.contains(&"..") {
+        return Err(invalid("the path must not contain `..` components"));
+    }
+
+    Ok(components)
+}
+
+/// Checks that the folder name is a single path component, so that the peer can't be tricked into writing outside
+/// of its download directory.
+fn validate_folder_name(folder_name: &str) -> Result<(), WormholeError> {
+    let invalid = |reason: &str| WormholeError::InvalidFolderEntry {
+        path: folder_name.into(),
+        reason: reason.into(),
+    };
+
+    if folder_name.is_empty() {
+        return Err(invalid("the folder name must not be empty"));
+    }
+    if folder_name.contains(['/', '\\']) {
+        return Err(invalid("the folder name must not contain path separators"));
+    }
+    if folder_name == "." || folder_name == ".." {
+        return Err(invalid("the folder name must not be `.` or `..`"));
+    }
+
+    Ok(())
+}
+
+/// Inserts a file into the (in-memory) folder tree, creating the intermediate directories as needed. Reading the file
+/// is limited by the throttle, which is shared by all the files of the folder.
+fn insert_entry(tree: &mut BTreeMap<String, OfferSendEntry>, path: &str, bytes: Vec<u8>, throttle: &Throttle) -> Result<(), WormholeError> {
+    let conflict = || WormholeError::InvalidFolderEntry {
+        path: path.into(),
+        reason: "the path conflicts with another entry".into(),
+    };
+    let components = path_components(path)?;
+    let (file_name, directories) = components.split_last().expect("the path has at least one component");
+
+    let mut tree = tree;
+    for &directory in directories {
+        let entry = tree.entry(directory.into()).or_insert_with(|| OfferSendEntry::Directory {
+            content: BTreeMap::new(),
+        });
+        tree = match entry {
+            OfferSendEntry::Directory { content } => content,
+            _ => return Err(conflict()),
+        };
+    }
+
+    match tree.entry((*file_name).into()) {
+        Entry::Occupied(_) => Err(conflict()),
+        Entry::Vacant(entry) => {
+            let size = bytes.len() as u64;
+            let bytes: Arc<[u8]> = bytes.into();
+            let throttle = throttle.clone();
+            entry.insert(OfferSendEntry::RegularFile {
+                size,
+                content: Box::new(move || {
+                    let reader = throttle.wrap(Cursor::new(bytes.clone()));
+                    Box::pin(async move { Ok(Box::new(reader) as Box<dyn AsyncRead + Unpin + Send>) })
+                        as future::BoxFuture<'static, io::Result<_>>
+                }),
+            });
+            Ok(())
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Wormhole {
+    #[wasm_bindgen]
+    /// Send a folder to the peer.
+    ///
+    /// All of the files are buffered in memory (both here and on the JS side), so this is only suitable for folders
+    /// that comfortably fit into the memory of the browser tab. The WASM memory is limited to 4 GiB.
+    ///
+    /// Resolves with the statistics of the transfer once it finished.
+    ///
+    /// This consumes the wormhole, as the transfer takes ownership of the connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `folder_name` - The name of the folder, as it will be offered to the peer. It must be a single path component,
+    ///   i.e. not contain `/` or `\`, and not be `.` or `..`.
+    /// * `entries` - An array of `{ path: string, bytes: Uint8Array }` objects, where `path` is relative to the folder
+    ///   and uses `/` as the separator. Paths containing `..` components are rejected.
+    /// * `progress` - An optional callback, invoked with `(sent, total)` as the transfer progresses.
+    /// * `cancel` - An optional token to cancel the transfer with. The peer is notified that the transfer was aborted.
+    pub async fn send_folder(self, folder_name: String, entries: JsValue, progress: Option<Function>, cancel: Option<&CancelToken>) -> Result<TransferStats, WormholeError> {
+        validate_folder_name(&folder_name)?;
+        let session = Session::register(cancel);
+        let cancel = session.token();
+        self.verify(cancel).await?;
+        let entries: Vec<FolderEntry> = serde_wasm_bindgen::from_value(entries).map_err(|e| WormholeError::InvalidFolderEntry {
+            path: folder_name.clone(),
+            reason: e.to_string(),
+        })?;
+
+        let total_size = entries.iter().map(|entry| entry.bytes.len() as u64).sum();
+        let throttle = Throttle::new(self.config.max_bytes_per_second);
+        let mut content = BTreeMap::new();
+        for entry in entries {
+            insert_entry(&mut content, &entry.path, entry.bytes.into_vec(), &throttle)?;
+        }
+        let offer = OfferSend::from(BTreeMap::from([(folder_name, OfferSendEntry::Directory { content })]));
+
+        let relay_hints = self.config.relay_hints()?;
+        let started = Date::now();
+        self.config.report_status(WormholeStatus::Transferring);
+        let send = |cancelled| {
+            transfer::send(
+                self.inner.into_inner(),
+                relay_hints,
+                self.config.transit_abilities(),
+                offer,
+                self.config.transit_handler(),
+                progress_handler(progress),
+                cancelled,
+            )
+        };
+
+        track(&self.config, cancellable_transfer(send, cancel), WormholeStatus::Done).await?;
+
+        let stats = TransferStats::since(total_size, started);
+        self.config.report_transferred(&stats);
+
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_components_skip_empty_and_current_components() {
+        assert_eq!(path_components("docs//./notes.txt").unwrap(), ["docs", "notes.txt"]);
+    }
+
+    #[test]
+    fn path_components_reject_escaping_paths() {
+        for path in ["../secret", "docs/../../secret", "/etc/passwd", "docs\\notes.txt", "", "./"] {
+            assert!(matches!(path_components(path), Err(WormholeError::InvalidFolderEntry { .. })), "{path}");
+        }
+    }
+
+    #[test]
+    fn insert_entry_creates_intermediate_directories() {
+        let mut tree = BTreeMap::new();
+        insert_entry(&mut tree, "docs/notes.txt", b"notes".to_vec(), &Throttle::new(None)).unwrap();
+        insert_entry(&mut tree, "docs/todo.txt", b"todo".to_vec(), &Throttle::new(None)).unwrap();
+
+        let Some(OfferSendEntry::Directory { content }) = tree.get("docs") else {
+            panic!("expected a directory");
+        };
+        assert!(matches!(content.get("notes.txt"), Some(OfferSendEntry::RegularFile { size: 5, .. })));
+        assert!(matches!(content.get("todo.txt"), Some(OfferSendEntry::RegularFile { size: 4, .. })));
+    }
+
+    #[test]
+    fn insert_entry_rejects_conflicts() {
+        let mut tree = BTreeMap::new();
+        insert_entry(&mut tree, "docs/notes.txt", Vec::new(), &Throttle::new(None)).unwrap();
+
+        // The same file twice, a file where a directory is, and a directory where a file is.
+        for path in ["docs/notes.txt", "docs", "docs/notes.txt/more.txt"] {
+            assert!(matches!(insert_entry(&mut tree, path, Vec::new(), &Throttle::new(None)), Err(WormholeError::InvalidFolderEntry { .. })), "{path}");
+        }
+    }
+
+    #[test]
+    fn insert_entry_rejects_escaping_paths() {
+        let mut tree = BTreeMap::new();
+
+        assert!(insert_entry(&mut tree, "../secret", Vec::new(), &Throttle::new(None)).is_err());
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn folder_names_must_be_single_components() {
+        assert!(validate_folder_name("photos").is_ok());
+        assert!(validate_folder_name("my photos.2024").is_ok());
+
+        for folder_name in ["", ".", "..", "a/b", "/photos", "a\\b"] {
+            assert!(matches!(validate_folder_name(folder_name), Err(WormholeError::InvalidFolderEntry { .. })), "{folder_name}");
+        }
+    }
+}
@@ -1,90 +1,148 @@
 use std::borrow::Cow;
-use std::fmt::{self, Formatter};
 use std::future::Future;
 use std::panic;
 use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
 
-use magic_wormhole::{AppConfig as WhAppConfig, AppID, Code, Wormhole as Wh, WormholeError as WhError};
-use magic_wormhole::transfer::AppVersion;
-use thiserror::Error;
+use futures::lock::Mutex;
+use futures::TryFutureExt;
+use js_sys::{Function, Promise};
+use magic_wormhole::{AppConfig as WhAppConfig, AppID, Code, Wormhole as Wh, WormholeError as WhError, WormholeWelcome as WhWelcome};
+use serde::Serialize;
 use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::future_to_promise;
+
+use cancel::{cancellable, with_timeout};
+use code::validate_code_length;
+use reconnect::with_retries;
+use session::Session;
+use status::{track, track_failure, WormholeStatus};
+
+mod cancel;
+mod code;
+mod compat;
+mod config;
+mod error;
+mod folder;
+mod message;
+mod reconnect;
+mod session;
+mod status;
+mod stream;
+#[cfg(all(test, target_arch = "wasm32"))]
+mod test_support;
+mod throttle;
+mod transfer;
+mod transit;
+mod uri;
+mod verifier;
+mod wordlist;
+
+pub use cancel::CancelToken;
+pub use code::{
+    code_entropy_bits, default_wordlist, generate_code, generate_code_seeded, is_code_length_safe, normalize_code, parse_code, validate_code,
+    CodeValidation, ParsedCode,
+};
+pub use config::{default_app_id, default_rendezvous_url, default_transit_relay_url, AppConfig, TransitMode};
+pub use error::{JsWormholeError, WormholeError, WormholeErrorKind};
+pub use reconnect::ReconnectPolicy;
+pub use session::{active_sessions, cancel_all};
+pub use stream::{ReadableStreamHandle, ReceivedFileMeta};
+pub use transfer::{ReceivedFile, TransferOffer, TransferStats};
+pub use transit::TransitInfo;
+pub use uri::{code_to_uri, uri_to_code, ParsedUri};
+pub use verifier::{verifier_emoji, verifier_hex, VerificationRequest};
+
+/// The revision of magic-wormhole.rs that these bindings are built against, as pinned in `Cargo.toml`.
+const MAGIC_WORMHOLE_VERSION: &str = "git-46eceb0";
+
+/// Guards the initialization, so that it only runs once.
+static INIT: Once = Once::new();
+/// Whether the initialization installed the console panic hook.
+static PANIC_HOOK_INSTALLED: AtomicBool = AtomicBool::new(false);
 
 #[wasm_bindgen(start)]
 /// Runs initialization stuff for the module.
 ///
 /// This function will execute automatically, and need not (and ideally should not) be called manually.
+/// Use `init` instead in environments where it doesn't run.
 pub fn _init() {
-    panic::set_hook(Box::new(console_error_panic_hook::hook));
+    init();
 }
 
 #[wasm_bindgen]
-/// Wormhole configuration corresponding to an upper layer protocol
+/// Initializes the module, i.e. installs a panic hook that logs panics to the console.
 ///
-/// There are multiple different protocols built on top of the core Wormhole protocol.
-/// They are identified by a unique URI-like ID string, an URL to find the rendezvous server (might be shared among multiple protocols),
-/// and client implementations also have a “version” data to do protocol negotiation.
-pub struct AppConfig {
-    id: String,
-    rendezvous_url: String,
-    // Placeholder, till I can figure out how to pass this to the actual wormhole config.
-    _app_version: serde_json::Value,
+/// This runs automatically when the module is instantiated. Call it manually in environments where that doesn't
+/// happen (e.g. some Node.js setups), before using the module. Initializing more than once is harmless.
+pub fn init() {
+    INIT.call_once(|| {
+        panic::set_hook(Box::new(console_error_panic_hook::hook));
+        PANIC_HOOK_INSTALLED.store(true, Ordering::Relaxed);
+    });
 }
 
 #[wasm_bindgen]
-impl AppConfig {
-    #[wasm_bindgen(constructor)]
-    pub fn new(id: String, rendezvous_url: String) -> Self {
-        Self {
-            id,
-            rendezvous_url,
-            // This is currently a placeholder field, so the value doesn't really matter.
-            _app_version: "".into(),
-        }
-    }
-
-    #[wasm_bindgen(getter)]
-    pub fn id(&self) -> String {
-        self.id.clone()
-    }
-
-    #[wasm_bindgen(setter)]
-    pub fn set_id(&mut self, id: String) {
-        self.id = id;
-    }
-
-    #[wasm_bindgen(getter)]
-    pub fn rendezvous_url(&self) -> String {
-        self.rendezvous_url.clone()
-    }
-
-    #[wasm_bindgen(setter)]
-    pub fn set_rendezvous_url(&mut self, rendezvous_url: String) {
-        self.rendezvous_url = rendezvous_url;
+/// Initializes the module like `init`, but without the console panic hook, for embedders that install their own
+/// panic reporter.
+///
+/// Exactly one panic hook should be active. As the module is initialized automatically when it is instantiated, this
+/// also removes the hook that was installed then, so call it before installing your own. `init` doesn't install the
+/// hook again afterwards.
+pub fn init_without_panic_hook() {
+    INIT.call_once(|| {});
+    if PANIC_HOOK_INSTALLED.swap(false, Ordering::Relaxed) {
+        drop(panic::take_hook());
     }
 }
 
-#[derive(Error, Debug)]
-pub struct WormholeError(#[from] WhError);
+#[wasm_bindgen]
+/// Routes the log output of the module (and of the underlying wormhole library) to the browser console.
+///
+/// Logging is disabled unless this is called. Calling it again only changes the level.
+///
+/// # Arguments
+///
+/// * `level` - The most verbose level to log, one of `"trace"`, `"debug"`, `"info"`, `"warn"` or `"error"`.
+pub fn init_logging(level: String) -> Result<(), WormholeError> {
+    let level = log::Level::from_str(&level).map_err(|_| WormholeError::InvalidConfig(format!("unknown log level `{level}`")))?;
+
+    // This only fails if a logger was already installed, in which case only the level needs updating.
+    let _ = console_log::init_with_level(level);
+    log::set_max_level(level.to_level_filter());
 
-impl fmt::Display for WormholeError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.0)
-    }
+    Ok(())
 }
 
-impl From<WormholeError> for JsValue {
-    fn from(value: WormholeError) -> Self {
-        value.to_string().into()
-    }
+/// Converts a JSON value into the equivalent plain JS value (objects rather than `Map`s).
+pub(crate) fn json_to_js(value: &serde_json::Value) -> JsValue {
+    value
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .unwrap_or(JsValue::NULL)
 }
 
 #[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
 /// The result of the client-server handshake.
 pub struct WormholeWelcome {
     /// A welcome message from the server (think of “message of the day”). Should be displayed to the user if present.
     welcome: Option<String>,
     /// The wormhole code.
     code: String,
+    /// The nameplate part of the wormhole code.
+    nameplate: String,
+}
+
+impl From<WhWelcome> for WormholeWelcome {
+    fn from(value: WhWelcome) -> Self {
+        Self {
+            welcome: value.welcome,
+            nameplate: value.code.nameplate().to_string(),
+            code: value.code.0,
+        }
+    }
 }
 
 #[wasm_bindgen]
@@ -98,34 +156,366 @@ impl WormholeWelcome {
     pub fn code(&self) -> String {
         self.code.clone()
     }
+
+    #[wasm_bindgen(getter)]
+    /// The nameplate part of the wormhole code (e.g. `7` for `7-guitarist-revenge`), as allocated by the server.
+    pub fn nameplate(&self) -> String {
+        self.nameplate.clone()
+    }
+
+    #[wasm_bindgen(js_name = toJSON)]
+    /// Converts the welcome into a plain object, so that `JSON.stringify` and `console.log` show its fields.
+    ///
+    /// The `welcome` field is `null` if the server didn't send a welcome message.
+    pub fn to_json(&self) -> JsValue {
+        self.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .unwrap_or(JsValue::NULL)
+    }
 }
 
 #[wasm_bindgen]
 /// Establishing Wormhole connection.
-pub struct Wormhole;
+///
+/// Instances of this type wrap an established wormhole, and are obtained from `WelcomeAndWormhole::wormhole`.
+///
+/// An established wormhole keeps a connection to the rendezvous server open until it is consumed (by a transfer or `close`)
+/// or freed. As the JS garbage collector doesn't run deterministically, call `free()` on wormholes that are no longer needed.
+pub struct Wormhole {
+    /// The core wormhole, which needs exclusive access to send or receive. It is locked for the duration of each
+    /// message exchange, so that the messaging methods only need a shared reference.
+    inner: Mutex<Wh>,
+    /// The verifier and the peer version, copied out of the core wormhole so that they are readable while it is locked.
+    verifier: Vec<u8>,
+    peer_version: serde_json::Value,
+    /// The configuration that the wormhole was established with, which subsequent transfers use.
+    config: AppConfig,
+}
 
 #[wasm_bindgen]
 /// Represents the awaitable handshake future that the `Wormhole::connect_without_code` function returns.
-pub struct Handshake(Pin<Box<dyn Future<Output=Result<Wh, WhError>>>>);
+///
+/// A pending handshake keeps a connection to the rendezvous server open. Calling `free()` on it (or on the
+/// `WelcomeAndHandshake` holding it) drops it, which closes the connection without waiting for the peer.
+pub struct Handshake {
+    future: Pin<Box<dyn Future<Output=Result<Wh, WhError>>>>,
+    config: AppConfig,
+    options: ConnectOptions,
+}
+
+/// The options that a handshake was connected with, which `WelcomeAndHandshake::retry_with_new_code` connects again with.
+struct ConnectOptions {
+    code_length: usize,
+    cancel: Option<CancelToken>,
+    timeout_ms: Option<f64>,
+    reconnect: Option<ReconnectPolicy>,
+}
+
+#[wasm_bindgen]
+impl Handshake {
+    #[wasm_bindgen]
+    /// Wait for the peer to connect and complete the handshake, like `WelcomeAndHandshake::finish`.
+    ///
+    /// This is meant for handshakes taken out with `WelcomeAndHandshake::handshake`, once the welcome was read.
+    ///
+    /// This consumes the object, as the handshake can only be completed once.
+    ///
+    /// # Arguments
+    ///
+    /// * `cancel` - An optional token to cancel waiting for the peer with.
+    pub async fn finish(self, cancel: Option<&CancelToken>) -> Result<Wormhole, WormholeError> {
+        let session = Session::register(cancel);
+        let cancel = session.token();
+        let Handshake { future, config, .. } = self;
+        let handshake = async {
+            let wh = cancellable(future, cancel).await?;
+            config.check_peer_version(&wh.peer_version)?;
+            Ok::<_, WormholeError>(wh)
+        };
+        let wh = track(&config, handshake, WormholeStatus::PeerConnected).await?;
+
+        Ok(Wormhole::new(wh, config))
+    }
+}
 
 #[wasm_bindgen]
 /// Represents the tuple containing the `WormholeWelcome` and the awaitable handshake future that the `Wormhole::connect_without_code`
 /// function returns.
+///
+/// Call `free()` on it if the handshake is abandoned, to close the connection to the rendezvous server promptly.
 pub struct WelcomeAndHandshake(WormholeWelcome, Handshake);
 
+#[wasm_bindgen]
+impl WelcomeAndHandshake {
+    #[wasm_bindgen(getter)]
+    pub fn welcome(&self) -> WormholeWelcome {
+        self.0.clone()
+    }
+
+    #[wasm_bindgen]
+    /// Takes the awaitable handshake out of the tuple, to be completed with `Handshake::finish`.
+    ///
+    /// This consumes the object, so it can no longer be used afterwards. Read the welcome before taking the handshake.
+    pub fn handshake(self) -> Handshake {
+        self.1
+    }
+
+    #[wasm_bindgen]
+    /// Wait for the peer to connect and complete the handshake.
+    ///
+    /// It returns the original "welcome" from the server along with the wormhole object.
+    ///
+    /// This is the "wait for peer" step: it resolves as soon as the key is established and the peer confirmed it,
+    /// before any transfer starts, so it can be used to show that the peer arrived. Transfers on the returned wormhole
+    /// reuse the established session, without negotiating again.
+    ///
+    /// This consumes the object, as the handshake can only be completed once.
+    ///
+    /// # Arguments
+    ///
+    /// * `cancel` - An optional token to cancel waiting for the peer with.
+    pub async fn finish(self, cancel: Option<&CancelToken>) -> Result<WelcomeAndWormhole, WormholeError> {
+        let WelcomeAndHandshake(welcome, handshake) = self;
+        let wormhole = handshake.finish(cancel).await?;
+
+        Ok(WelcomeAndWormhole::new(welcome, wormhole))
+    }
+
+    #[wasm_bindgen]
+    /// Abandon this handshake, and connect again with a newly generated code, using the same configuration and options.
+    ///
+    /// This is meant for the "try again" flow, e.g. when the peer got the code wrong. The pending handshake is dropped,
+    /// which closes its connection to the rendezvous server (so the old code stops working), and `on_code` is invoked
+    /// with the new code as soon as it is known. The new connection attempt uses the cancel token, timeout and reconnect
+    /// policy that were passed to `connect_without_code`.
+    ///
+    /// This consumes the object; use the returned one to continue.
+    ///
+    /// # Arguments
+    ///
+    /// * `on_code` - A callback, invoked with the new code.
+    /// * `code_length` - The number of words to include in the new code. Defaults to the length of the old code.
+    pub async fn retry_with_new_code(self, on_code: Function, code_length: Option<usize>) -> Result<WelcomeAndHandshake, WormholeError> {
+        let WelcomeAndHandshake(_, Handshake { future, config, options }) = self;
+        drop(future);
+
+        let code_length = code_length.unwrap_or(options.code_length);
+        let retried = Wormhole::connect_without_code(&config, code_length, options.cancel.as_ref(), options.timeout_ms, options.reconnect.as_ref()).await?;
+        let _ = on_code.call1(&JsValue::NULL, &retried.0.code.clone().into());
+
+        Ok(retried)
+    }
+
+    #[wasm_bindgen]
+    /// Returns the handshake as a promise, which resolves with the wormhole once the peer connected.
+    ///
+    /// This is equivalent to `finish(undefined)` followed by `WelcomeAndWormhole::wormhole`, but can be awaited or
+    /// chained with `then` directly. The promise rejects with a `WormholeError` if the handshake fails.
+    ///
+    /// This consumes the object, as the handshake can only be completed once.
+    pub fn handshake_promise(self) -> Promise {
+        future_to_promise(async move {
+            let (_, wormhole) = self.finish(None).await?.into_parts()?;
+
+            Ok(wormhole.into())
+        })
+    }
+}
+
 #[wasm_bindgen]
 /// Represents the tuple containing the `WormholeWelcome` and the `Wormhole` object that the `Wormhole::connect_with_code`
 /// function returns.
-pub struct WelcomeAndWormhole(WormholeWelcome, Wh);
+///
+/// The welcome stays readable after the wormhole was taken out.
+pub struct WelcomeAndWormhole(WormholeWelcome, Option<Wormhole>);
+
+impl WelcomeAndWormhole {
+    fn new(welcome: WormholeWelcome, wormhole: Wormhole) -> Self {
+        Self(welcome, Some(wormhole))
+    }
+
+    /// Splits the tuple into the welcome and the wormhole, failing if the wormhole was already taken.
+    pub(crate) fn into_parts(mut self) -> Result<(WormholeWelcome, Wormhole), WormholeError> {
+        let wormhole = self.wormhole()?;
+
+        Ok((self.0, wormhole))
+    }
+}
+
+#[wasm_bindgen]
+impl WelcomeAndWormhole {
+    #[wasm_bindgen(getter)]
+    pub fn welcome(&self) -> WormholeWelcome {
+        self.0.clone()
+    }
+
+    #[wasm_bindgen]
+    /// Takes the wormhole out of the tuple.
+    ///
+    /// This can only be done once, as the wormhole can't be shared. The welcome remains available afterwards.
+    pub fn wormhole(&mut self) -> Result<Wormhole, WormholeError> {
+        self.1.take().ok_or(WormholeError::WormholeTaken)
+    }
+}
+
+/// Either side of a connection attempt made with `Wormhole::connect`.
+enum MaybeHandshake {
+    /// A code was generated, and the handshake is still waiting for the peer.
+    Pending(Handshake),
+    /// A code was provided, and the wormhole is already established.
+    Established(Box<Wormhole>),
+}
+
+#[wasm_bindgen]
+/// Represents the tuple containing the `WormholeWelcome` and either a pending handshake or an established wormhole,
+/// which the `Wormhole::connect` function returns.
+///
+/// Call `finish` to get an established wormhole either way.
+pub struct WelcomeAndMaybeHandshake(WormholeWelcome, MaybeHandshake);
+
+#[wasm_bindgen]
+impl WelcomeAndMaybeHandshake {
+    #[wasm_bindgen(getter)]
+    pub fn welcome(&self) -> WormholeWelcome {
+        self.0.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    /// Whether the wormhole is already established, i.e. `finish` won't wait for the peer.
+    pub fn is_established(&self) -> bool {
+        matches!(self.1, MaybeHandshake::Established(_))
+    }
+
+    #[wasm_bindgen]
+    /// Wait for the peer to connect if needed, and complete the handshake.
+    ///
+    /// It returns the original "welcome" from the server along with the wormhole object.
+    ///
+    /// This consumes the object, as the handshake can only be completed once.
+    ///
+    /// # Arguments
+    ///
+    /// * `cancel` - An optional token to cancel waiting for the peer with.
+    pub async fn finish(self, cancel: Option<&CancelToken>) -> Result<WelcomeAndWormhole, WormholeError> {
+        match self {
+            WelcomeAndMaybeHandshake(welcome, MaybeHandshake::Pending(handshake)) => WelcomeAndHandshake(welcome, handshake).finish(cancel).await,
+            WelcomeAndMaybeHandshake(welcome, MaybeHandshake::Established(wormhole)) => Ok(WelcomeAndWormhole::new(welcome, *wormhole)),
+        }
+    }
+}
+
+impl Wormhole {
+    /// Connects to a peer with a code. This is the shared implementation of the `connect_with_*` functions.
+    async fn connect_with(config: &AppConfig, code: Code, expect_claimed_nameplate: Option<bool>, cancel: Option<&CancelToken>, timeout_ms: Option<f64>, reconnect: Option<&ReconnectPolicy>) -> Result<WelcomeAndWormhole, WormholeError> {
+        let session = Session::register(cancel);
+        let cancel = session.token();
+        let expect_claimed_nameplate = expect_claimed_nameplate.unwrap_or(false);
+        config.report_status(WormholeStatus::Connecting);
+        let connect = with_retries(reconnect, || {
+            let wh_config = Self::get_wh_config(config);
+            with_timeout(Wh::connect_with_code(wh_config, code.clone(), expect_claimed_nameplate).err_into(), timeout_ms)
+        });
+        let handshake = async {
+            let (welcome, wh) = cancellable(connect, cancel).await?;
+            config.check_peer_version(&wh.peer_version)?;
+            Ok::<_, WormholeError>((welcome, wh))
+        };
+        let (welcome, wh) = track(config, handshake, WormholeStatus::PeerConnected).await?;
+        let welcome = WormholeWelcome::from(welcome);
+        config.report_motd(&welcome);
+
+        Ok(WelcomeAndWormhole::new(welcome, Wormhole::new(wh, config.clone())))
+    }
+
+    /// Wraps an established core wormhole.
+    fn new(wh: Wh, config: AppConfig) -> Self {
+        Self {
+            verifier: wh.verifier.to_vec(),
+            peer_version: wh.peer_version.clone(),
+            inner: Mutex::new(wh),
+            config,
+        }
+    }
+}
 
 #[wasm_bindgen]
 impl Wormhole {
     /// Generates a core wormhole AppConfig from the provided custom AppConfig.
-    fn get_wh_config(config: &AppConfig) -> WhAppConfig<AppVersion> {
+    fn get_wh_config(config: &AppConfig) -> WhAppConfig<serde_json::Value> {
         WhAppConfig {
             id: AppID(Cow::from(config.id.clone())),
             rendezvous_url: Cow::from(config.rendezvous_url.clone()),
-            app_version: AppVersion {},
+            app_version: config.app_version.clone(),
+        }
+    }
+
+    #[wasm_bindgen]
+    /// Returns the names of the transit abilities that transfers can negotiate in this build.
+    ///
+    /// Browsers can't open raw TCP connections, so the WASM build can only reach the peer through a websocket relay
+    /// (`relay-v2`); `TransitMode.DirectOnly` always fails there. Native builds also support `direct-tcp-v1` and `relay-v1`.
+    pub fn supported_transit_abilities() -> Vec<String> {
+        let abilities: &[&str] = if cfg!(target_arch = "wasm32") {
+            &["relay-v2"]
+        } else {
+            &["direct-tcp-v1", "relay-v1", "relay-v2"]
+        };
+
+        abilities.iter().map(|&ability| ability.into()).collect()
+    }
+
+    #[wasm_bindgen]
+    /// Whether transfers in this build can connect to the peer directly, rather than through a relay.
+    ///
+    /// This is `false` for the WASM build, where `TransitMode.DirectOnly` can't work, so UIs should hide that option.
+    pub fn can_connect_directly() -> bool {
+        !cfg!(target_arch = "wasm32")
+    }
+
+    #[wasm_bindgen]
+    /// Returns the versions in use, for bug reports and compatibility checks.
+    ///
+    /// The object has the `crate_version` of these bindings, the `magic_wormhole_version` (the pinned revision of the
+    /// underlying library) and the supported `transfer_protocols`.
+    pub fn version() -> JsValue {
+        json_to_js(&serde_json::json!({
+            "crate_version": env!("CARGO_PKG_VERSION"),
+            "magic_wormhole_version": MAGIC_WORMHOLE_VERSION,
+            "transfer_protocols": ["transfer-v1"],
+        }))
+    }
+
+    #[wasm_bindgen]
+    /// Connect to the rendezvous server, either with a code or by generating one.
+    ///
+    /// This unifies `connect_with_code` and `connect_without_code`: exactly one of `code` and `code_length` must be given.
+    /// It returns the "welcome" from the server along with either the established wormhole or the pending handshake.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The app configuration.
+    /// * `code` - The wormhole code to connect with.
+    /// * `code_length` - The number of words to include in the generated wormhole code.
+    /// * `cancel` - An optional token to cancel the connection attempt with.
+    /// * `timeout_ms` - An optional timeout for the connection attempt, in milliseconds. With `reconnect`, it applies to each attempt.
+    /// * `reconnect` - An optional policy for retrying to connect if the connection to the server fails.
+    pub async fn connect(
+        config: &AppConfig,
+        code: Option<String>,
+        code_length: Option<usize>,
+        cancel: Option<&CancelToken>,
+        timeout_ms: Option<f64>,
+        reconnect: Option<&ReconnectPolicy>,
+    ) -> Result<WelcomeAndMaybeHandshake, WormholeError> {
+        match (code, code_length) {
+            (Some(code), None) => {
+                let (welcome, wormhole) = Self::connect_with_code(config, &code, None, cancel, timeout_ms, reconnect).await?.into_parts()?;
+                Ok(WelcomeAndMaybeHandshake(welcome, MaybeHandshake::Established(Box::new(wormhole))))
+            }
+            (None, Some(code_length)) => {
+                let WelcomeAndHandshake(welcome, handshake) = Self::connect_without_code(config, code_length, cancel, timeout_ms, reconnect).await?;
+                Ok(WelcomeAndMaybeHandshake(welcome, MaybeHandshake::Pending(handshake)))
+            }
+            _ => Err(WormholeError::InvalidConfig("exactly one of `code` and `code_length` must be given".into())),
         }
     }
 
@@ -137,41 +527,238 @@ impl Wormhole {
     /// # Arguments
     ///
     /// * `config` - The app configuration.
-    /// * `code_length` - The number of words to include in the generated wormhole code.
-    pub async fn connect_without_code(config: &AppConfig, code_length: usize) -> Result<WelcomeAndHandshake, WormholeError> {
-        let config = Self::get_wh_config(&config);
-        let (welcome, handshake) = Wh::connect_without_code(config, code_length).await?;
+    /// * `code_length` - The number of words to include in the generated wormhole code, between 1 and 32.
+    ///   2 is recommended, see `is_code_length_safe`.
+    /// * `cancel` - An optional token to cancel the connection attempt with.
+    /// * `timeout_ms` - An optional timeout for connecting to the rendezvous server, in milliseconds.
+    ///   It doesn't cover waiting for the peer in `WelcomeAndHandshake::finish`. With `reconnect`, it applies to each attempt.
+    /// * `reconnect` - An optional policy for retrying to connect if the connection to the server fails.
+    pub async fn connect_without_code(config: &AppConfig, code_length: usize, cancel: Option<&CancelToken>, timeout_ms: Option<f64>, reconnect: Option<&ReconnectPolicy>) -> Result<WelcomeAndHandshake, WormholeError> {
+        validate_code_length(code_length)?;
+        let options = ConnectOptions {
+            code_length,
+            cancel: cancel.cloned(),
+            timeout_ms,
+            reconnect: reconnect.cloned(),
+        };
+        let session = Session::register(cancel);
+        let cancel = session.token();
+        config.report_status(WormholeStatus::Connecting);
+        let connect = with_retries(reconnect, || {
+            let wh_config = Self::get_wh_config(config);
+            with_timeout(Wh::connect_without_code(wh_config, code_length).err_into(), timeout_ms)
+        });
+        let (welcome, handshake) = track_failure(config, cancellable(connect, cancel)).await?;
+        let welcome = WormholeWelcome::from(welcome);
+        // The code is generated before the wait for the peer starts.
+        config.report_code(&welcome);
+        config.report_status(WormholeStatus::WaitingForPeer);
+        config.report_motd(&welcome);
 
         Ok(WelcomeAndHandshake(
-            WormholeWelcome {
-                welcome: welcome.welcome,
-                code: welcome.code.0,
+            welcome,
+            Handshake {
+                future: Box::pin(handshake),
+                config: config.clone(),
+                options,
             },
-            Handshake(Box::pin(handshake)),
         ))
     }
 
     #[wasm_bindgen]
     /// Connect to a peer with a code.
     ///
-    /// It returns the "welcome" from the server along with the wormhole object.
+    /// It returns the "welcome" from the server along with the wormhole object, once the key is established with the
+    /// peer. Transfers on the returned wormhole reuse the established session.
     ///
     /// # Arguments
     ///
     /// * `config` - The app configuration.
-    /// * `code` - The wormhole code.
+    /// * `code` - The wormhole code. It is normalized with `normalize_code` first.
     /// * `expect_claimed_nameplate` - Whether or not to expect a claimed nameplate. Defaults to `false`.
-    pub async fn connect_with_code(config: &AppConfig, code: &str, expect_claimed_nameplate: Option<bool>) -> Result<WelcomeAndWormhole, WormholeError> {
-        let expect_claimed_nameplate = expect_claimed_nameplate.unwrap_or(false);
-        let config = Self::get_wh_config(&config);
-        let (welcome, wh) = Wh::connect_with_code(config, Code(code.to_string()), expect_claimed_nameplate).await?;
+    /// * `cancel` - An optional token to cancel the connection attempt with.
+    /// * `timeout_ms` - An optional timeout for the handshake, in milliseconds. It doesn't cover subsequent transfers.
+    ///   With `reconnect`, it applies to each attempt.
+    /// * `reconnect` - An optional policy for retrying to connect if the connection to the server fails.
+    pub async fn connect_with_code(config: &AppConfig, code: &str, expect_claimed_nameplate: Option<bool>, cancel: Option<&CancelToken>, timeout_ms: Option<f64>, reconnect: Option<&ReconnectPolicy>) -> Result<WelcomeAndWormhole, WormholeError> {
+        let normalized = normalize_code(code);
+        if normalized.is_empty() {
+            return Err(WormholeError::InvalidCode {
+                code: code.into(),
+                reason: "the code must not be empty".into(),
+            });
+        }
 
-        Ok(WelcomeAndWormhole(
-            WormholeWelcome {
-                welcome: welcome.welcome,
-                code: welcome.code.0,
-            },
-            wh,
-        ))
+        Self::connect_with(config, Code(normalized), expect_claimed_nameplate, cancel, timeout_ms, reconnect).await
+    }
+
+    #[wasm_bindgen]
+    /// Connect to a peer with a code that was parsed with `parse_code`.
+    ///
+    /// This behaves exactly like `connect_with_code`, but avoids converting the parsed code back into a string.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The app configuration.
+    /// * `parsed` - The parsed wormhole code.
+    /// * `expect_claimed_nameplate` - Whether or not to expect a claimed nameplate. Defaults to `false`.
+    /// * `cancel` - An optional token to cancel the connection attempt with.
+    /// * `timeout_ms` - An optional timeout for the handshake, in milliseconds. It doesn't cover subsequent transfers.
+    ///   With `reconnect`, it applies to each attempt.
+    /// * `reconnect` - An optional policy for retrying to connect if the connection to the server fails.
+    pub async fn connect_with_parsed_code(config: &AppConfig, parsed: &ParsedCode, expect_claimed_nameplate: Option<bool>, cancel: Option<&CancelToken>, timeout_ms: Option<f64>, reconnect: Option<&ReconnectPolicy>) -> Result<WelcomeAndWormhole, WormholeError> {
+        Self::connect_with(config, parsed.to_code(), expect_claimed_nameplate, cancel, timeout_ms, reconnect).await
+    }
+
+    #[wasm_bindgen(getter)]
+    /// The verifier of the established wormhole.
+    ///
+    /// This is the raw key-confirmation material, which is derived from the session key and is identical on both sides.
+    /// Users can compare it out-of-band to make sure that nobody intercepted the connection.
+    /// It is not meant to be displayed as is; use `verifier_hex` or `verifier_emoji` instead.
+    pub fn verifier(&self) -> Vec<u8> {
+        self.verifier.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    /// The app version that the peer advertised during the handshake.
+    ///
+    /// This can be used to detect which features the peer supports. It is `null` if the peer didn't advertise a version.
+    pub fn peer_version(&self) -> JsValue {
+        match &self.peer_version {
+            serde_json::Value::Null => JsValue::NULL,
+            peer_version => json_to_js(peer_version),
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    /// The capability flags that the peer advertised with `AppConfig.set_app_version_flags`.
+    ///
+    /// It is `null` if the peer didn't advertise any flags.
+    pub fn peer_abilities(&self) -> JsValue {
+        self.peer_version
+            .get(config::ABILITIES_KEY)
+            .map_or(JsValue::NULL, json_to_js)
+    }
+
+    #[wasm_bindgen(getter)]
+    /// The device name that the peer advertised with `AppConfig.set_device_name`.
+    ///
+    /// It is `undefined` if the peer didn't advertise a name. As it comes from the peer, it is sanitized the same way
+    /// as when setting it, but should still be displayed as untrusted text.
+    pub fn peer_device_name(&self) -> Option<String> {
+        self.peer_version
+            .get(config::DEVICE_NAME_KEY)
+            .and_then(serde_json::Value::as_str)
+            .map(config::sanitize_device_name)
+            .filter(|name| !name.is_empty())
+    }
+
+    #[wasm_bindgen]
+    /// Close the wormhole, releasing the mailbox on the rendezvous server.
+    ///
+    /// This consumes the wormhole, so it can no longer be used afterwards.
+    /// Dropping the wormhole (or calling `free()` on it) also releases the connection, but only closing it explicitly
+    /// tells the server that the session ended cleanly.
+    pub async fn close(self) -> Result<(), WormholeError> {
+        self.inner.into_inner().close().await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod peer_tests {
+    use js_sys::{Function, JSON};
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::test_support::{connect_pair, connect_pair_with, local_config};
+    use crate::{parse_code, CancelToken, Wormhole, WormholeErrorKind};
+
+    #[wasm_bindgen_test]
+    async fn connect_without_code_rejects_unsupported_code_lengths() {
+        // The length is checked before connecting, so this doesn't need a server.
+        for code_length in [0, 33] {
+            let error = Wormhole::connect_without_code(&local_config(), code_length, None, None, None).await.err().unwrap();
+            assert_eq!(error.kind(), WormholeErrorKind::Config);
+            assert_eq!(error.to_string(), format!("Invalid code length {code_length}: must be between 1 and 32"));
+        }
+    }
+
+    #[wasm_bindgen_test]
+    #[ignore = "needs a local rendezvous server and transit relay, see `test_support`"]
+    async fn mismatched_codes_fail_with_wrong_code() {
+        let config = local_config();
+        let welcome_and_handshake = Wormhole::connect_without_code(&config, 2, None, None, None).await.unwrap();
+        let nameplate = welcome_and_handshake.welcome().nameplate();
+        let wrong_code = format!("{nameplate}-not-matching");
+
+        let (sender, receiver) = futures::join!(
+            welcome_and_handshake.finish(None),
+            Wormhole::connect_with_code(&config, &wrong_code, None, None, None, None)
+        );
+
+        for error in [sender.err().unwrap(), receiver.err().unwrap()] {
+            assert_eq!(error.kind(), WormholeErrorKind::WrongCode);
+            assert!(!error.is_retryable());
+        }
+    }
+
+    #[wasm_bindgen_test]
+    #[ignore = "needs a local rendezvous server and transit relay, see `test_support`"]
+    async fn peers_see_the_advertised_flags_and_device_name() {
+        let mut sender_config = local_config();
+        sender_config.set_app_version_flags(JSON::parse(r#"{ "supports_resume": true }"#).unwrap()).unwrap();
+        sender_config.set_device_name("laptop".into());
+        // Setting the app version afterwards keeps both.
+        sender_config.set_app_version(JSON::parse(r#"{ "custom": 1 }"#).unwrap()).unwrap();
+
+        let (_sender, receiver) = connect_pair_with(&sender_config, &local_config()).await;
+        let abilities = JSON::stringify(&receiver.peer_abilities()).unwrap();
+        assert_eq!(String::from(abilities), r#"{"supports_resume":true}"#);
+        assert_eq!(receiver.peer_device_name().as_deref(), Some("laptop"));
+    }
+
+    #[wasm_bindgen_test]
+    #[ignore = "needs a local rendezvous server and transit relay, see `test_support`"]
+    async fn taken_handshakes_can_be_finished() {
+        let config = local_config();
+        let welcome_and_handshake = Wormhole::connect_without_code(&config, 2, None, None, None).await.unwrap();
+        let code = welcome_and_handshake.welcome().code();
+        let handshake = welcome_and_handshake.handshake();
+
+        let (sender, receiver) = futures::join!(handshake.finish(None), Wormhole::connect_with_code(&config, &code, None, None, None, None));
+        let (_, receiver) = receiver.unwrap().into_parts().unwrap();
+        assert_eq!(sender.unwrap().verifier(), receiver.verifier());
+    }
+
+    #[wasm_bindgen_test]
+    #[ignore = "needs a local rendezvous server and transit relay, see `test_support`"]
+    async fn retrying_keeps_the_code_length() {
+        let welcome_and_handshake = Wormhole::connect_without_code(&local_config(), 3, None, None, None).await.unwrap();
+        let retried = welcome_and_handshake.retry_with_new_code(Function::new_no_args(""), None).await.unwrap();
+
+        assert_eq!(parse_code(&retried.welcome().code()).unwrap().words().len(), 3);
+    }
+
+    #[wasm_bindgen_test]
+    #[ignore = "needs a local rendezvous server and transit relay, see `test_support`"]
+    async fn retrying_keeps_the_cancel_token() {
+        let token = CancelToken::new();
+        let welcome_and_handshake = Wormhole::connect_without_code(&local_config(), 2, Some(&token), None, None).await.unwrap();
+        token.cancel();
+
+        let error = welcome_and_handshake.retry_with_new_code(Function::new_no_args(""), None).await.err().unwrap();
+        assert_eq!(error.kind(), WormholeErrorKind::Cancelled);
+    }
+
+    #[wasm_bindgen_test]
+    #[ignore = "needs a local rendezvous server and transit relay, see `test_support`"]
+    async fn both_peers_can_close() {
+        let (sender, receiver) = connect_pair(&local_config()).await;
+
+        let (sender, receiver) = futures::join!(sender.close(), receiver.close());
+        sender.unwrap();
+        receiver.unwrap();
     }
 }
@@ -0,0 +1,306 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::stream::parse_size;
+use crate::{json_to_js, Wormhole, WormholeError};
+
+/// The size of the length prefix of framed messages.
+const FRAME_HEADER_LEN: usize = 4;
+/// The maximum number of bytes sent in a single mailbox message, as the rendezvous server relays every message whole.
+const FRAME_CHUNK_LEN: usize = 64 * 1024;
+/// The default limit for the length of received framed messages.
+const DEFAULT_MAX_FRAME_LEN: u64 = 16 * 1024 * 1024;
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", content = "payload", rename_all = "kebab-case")]
+/// The envelope of structured messages, tagged with their type.
+enum PeerMessage {
+    /// An arbitrary JSON value, sent with `send_json`.
+    Json(serde_json::Value),
+}
+
+/// Splits a message into the chunks of its frame, i.e. the length prefix followed by the message.
+fn frame_chunks(data: &[u8]) -> Result<Vec<Vec<u8>>, WormholeError> {
+    let len = u32::try_from(data.len()).map_err(|_| WormholeError::FrameTooLarge {
+        size: data.len() as u64,
+        max: u32::MAX.into(),
+    })?;
+    let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + data.len());
+    frame.extend_from_slice(&len.to_be_bytes());
+    frame.extend_from_slice(data);
+
+    Ok(frame.chunks(FRAME_CHUNK_LEN).map(<[u8]>::to_vec).collect())
+}
+
+/// Reassembles a framed message from the chunks it was split into.
+///
+/// The chunks of messages that exceed the limit are still counted, but discarded, so that the whole frame is consumed.
+struct FrameReader {
+    len: u64,
+    max_len: u64,
+    received: u64,
+    data: Vec<u8>,
+}
+
+impl FrameReader {
+    /// Starts reassembling a message from the first chunk of its frame.
+    fn new(mut first_chunk: Vec<u8>, max_len: u64) -> Result<Self, WormholeError> {
+        if first_chunk.len() < FRAME_HEADER_LEN {
+            return Err(WormholeError::InvalidFrame(format!(
+                "expected a {FRAME_HEADER_LEN} byte length prefix, got {} bytes",
+                first_chunk.len()
+            )));
+        }
+        let header: [u8; FRAME_HEADER_LEN] = first_chunk[..FRAME_HEADER_LEN].try_into().expect("the header has a fixed length");
+        let mut reader = Self {
+            len: u32::from_be_bytes(header).into(),
+            max_len,
+            received: 0,
+            data: Vec::new(),
+        };
+        reader.push(first_chunk.split_off(FRAME_HEADER_LEN));
+
+        Ok(reader)
+    }
+
+    /// Whether the whole frame was received.
+    fn is_complete(&self) -> bool {
+        self.received >= self.len
+    }
+
+    fn push(&mut self, chunk: Vec<u8>) {
+        self.received += chunk.len() as u64;
+        if self.len <= self.max_len {
+            self.data.extend(chunk);
+        }
+    }
+
+    /// Returns the message, failing if it exceeds the limit, or if more bytes than declared were received.
+    fn finish(self) -> Result<Vec<u8>, WormholeError> {
+        if self.len > self.max_len {
+            return Err(WormholeError::FrameTooLarge {
+                size: self.len,
+                max: self.max_len,
+            });
+        }
+        if self.received != self.len {
+            return Err(WormholeError::InvalidFrame(format!("expected {} bytes, got {}", self.len, self.received)));
+        }
+
+        Ok(self.data)
+    }
+}
+
+#[wasm_bindgen]
+impl Wormhole {
+    #[wasm_bindgen]
+    /// Send an encrypted message to the peer.
+    ///
+    /// Messages are delivered in the order they were sent.
+    ///
+    /// The messaging methods can be called without awaiting the previous call, but they share one connection, so
+    /// they run one after the other, in call order. In particular, a pending receive holds up later sends until a
+    /// message arrives, so don't wait for a message that the peer only sends in response to a later send.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The contents of the message.
+    pub async fn send_message(&self, data: Vec<u8>) -> Result<(), WormholeError> {
+        self.inner.lock().await.send(data).await?;
+
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    /// Receive an encrypted message from the peer.
+    ///
+    /// Messages are received in the order they were sent. If no message is pending, this waits for the next one
+    /// (see `send_message` for how this interacts with concurrent sends).
+    pub async fn receive_message(&self) -> Result<Vec<u8>, WormholeError> {
+        Ok(self.inner.lock().await.receive().await?)
+    }
+
+    #[wasm_bindgen]
+    /// Send a length-prefixed message to the peer, to be received with `receive_framed`.
+    ///
+    /// Unlike `send_message`, large messages are split into multiple mailbox messages, and reassembled by the receiver.
+    /// The chunks are sent together, even if other messages are sent concurrently, but the peer has to receive them
+    /// with `receive_framed`.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The contents of the message.
+    pub async fn send_framed(&self, data: Vec<u8>) -> Result<(), WormholeError> {
+        let chunks = frame_chunks(&data)?;
+        let mut wh = self.inner.lock().await;
+        for chunk in chunks {
+            wh.send(chunk).await?;
+        }
+
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    /// Receive a message that the peer sent with `send_framed`.
+    ///
+    /// This waits until the whole message arrived. It fails with `FrameTooLarge` if the declared length exceeds
+    /// `max_len`. The rest of that message is still received (and discarded, so memory use stays bounded), so that
+    /// the next call starts at the next message.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_len` - The maximum length of the message, in bytes. Defaults to 16 MiB.
+    pub async fn receive_framed(&self, max_len: Option<f64>) -> Result<Vec<u8>, WormholeError> {
+        let max_len = max_len.map(parse_size).transpose()?.unwrap_or(DEFAULT_MAX_FRAME_LEN);
+
+        let mut wh = self.inner.lock().await;
+        let mut reader = FrameReader::new(wh.receive().await?, max_len)?;
+        while !reader.is_complete() {
+            reader.push(wh.receive().await?);
+        }
+
+        reader.finish()
+    }
+
+    #[wasm_bindgen]
+    /// Send a JSON-compatible value (e.g. an object) to the peer, to be received with `receive_json`.
+    ///
+    /// The value is sent as a single message, wrapped in a `{ "type": "json", "payload": ... }` envelope.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to send. It must be representable as JSON, i.e. not contain functions, `Map`s or the like.
+    pub async fn send_json(&self, value: JsValue) -> Result<(), WormholeError> {
+        let payload = serde_wasm_bindgen::from_value(value).map_err(|e| WormholeError::InvalidMessage(e.to_string()))?;
+        let message = serde_json::to_vec(&PeerMessage::Json(payload)).expect("JSON values always serialize");
+        self.inner.lock().await.send(message).await?;
+
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    /// Receive a value that the peer sent with `send_json`.
+    ///
+    /// If no message is pending, this waits for the next one. It fails if the message isn't a JSON message.
+    pub async fn receive_json(&self) -> Result<JsValue, WormholeError> {
+        let message = self.inner.lock().await.receive().await?;
+        let PeerMessage::Json(payload) = serde_json::from_slice(&message).map_err(|e| WormholeError::InvalidMessage(e.to_string()))?;
+
+        Ok(json_to_js(&payload))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sends the chunks of the messages through a frame reader, as `receive_framed` does.
+    fn read_frames(chunks: Vec<Vec<u8>>, max_len: u64) -> Vec<Result<Vec<u8>, WormholeError>> {
+        let mut chunks = chunks.into_iter();
+        let mut messages = Vec::new();
+        while let Some(first_chunk) = chunks.next() {
+            let message = FrameReader::new(first_chunk, max_len).and_then(|mut reader| {
+                while !reader.is_complete() {
+                    reader.push(chunks.next().expect("the frame is incomplete"));
+                }
+                reader.finish()
+            });
+            messages.push(message);
+        }
+
+        messages
+    }
+
+    #[test]
+    fn empty_messages_round_trip() {
+        let chunks = frame_chunks(&[]).unwrap();
+        assert_eq!(chunks, [vec![0, 0, 0, 0]]);
+
+        let messages = read_frames(chunks, DEFAULT_MAX_FRAME_LEN);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].as_ref().unwrap(), &Vec::<u8>::new());
+    }
+
+    #[test]
+    fn large_messages_round_trip_in_chunks() {
+        let data: Vec<u8> = (0..3 * FRAME_CHUNK_LEN).map(|i| i as u8).collect();
+        let chunks = frame_chunks(&data).unwrap();
+        assert_eq!(chunks.len(), 4);
+        assert!(chunks.iter().all(|chunk| chunk.len() <= FRAME_CHUNK_LEN));
+
+        let messages = read_frames(chunks, DEFAULT_MAX_FRAME_LEN);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].as_ref().unwrap(), &data);
+    }
+
+    #[test]
+    fn too_large_messages_are_skipped_whole() {
+        let large = vec![1; 2 * FRAME_CHUNK_LEN];
+        let mut chunks = frame_chunks(&large).unwrap();
+        chunks.extend(frame_chunks(b"next").unwrap());
+
+        let messages = read_frames(chunks, 1024);
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(messages[0], Err(WormholeError::FrameTooLarge { size, max: 1024 }) if size == large.len() as u64));
+        assert_eq!(messages[1].as_ref().unwrap(), b"next");
+    }
+
+    #[test]
+    fn short_headers_are_invalid() {
+        assert!(matches!(FrameReader::new(vec![0, 0], DEFAULT_MAX_FRAME_LEN), Err(WormholeError::InvalidFrame(_))));
+    }
+
+    #[test]
+    fn overlong_frames_are_invalid() {
+        let mut chunk = frame_chunks(b"abc").unwrap().remove(0);
+        chunk.push(b'd');
+
+        let reader = FrameReader::new(chunk, DEFAULT_MAX_FRAME_LEN).unwrap();
+        assert!(reader.is_complete());
+        assert!(matches!(reader.finish(), Err(WormholeError::InvalidFrame(_))));
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod peer_tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::test_support::{connect_pair, local_config};
+
+    #[wasm_bindgen_test]
+    #[ignore = "needs a local rendezvous server and transit relay, see `test_support`"]
+    async fn concurrent_calls_run_in_call_order() {
+        let (sender, receiver) = connect_pair(&local_config()).await;
+
+        let (first, second, third) = futures::join!(
+            sender.send_message(b"first".to_vec()),
+            sender.send_framed(vec![7; 200 * 1024]),
+            sender.send_message(b"third".to_vec())
+        );
+        first.unwrap();
+        second.unwrap();
+        third.unwrap();
+
+        assert_eq!(receiver.receive_message().await.unwrap(), b"first");
+        assert_eq!(receiver.receive_framed(None).await.unwrap(), vec![7; 200 * 1024]);
+        assert_eq!(receiver.receive_message().await.unwrap(), b"third");
+    }
+
+    #[wasm_bindgen_test]
+    #[ignore = "needs a local rendezvous server and transit relay, see `test_support`"]
+    async fn sending_and_receiving_can_overlap() {
+        let (alice, bob) = connect_pair(&local_config()).await;
+
+        // Alice's send is queued behind Alice's receive, which completes once Bob's message arrives.
+        let (received_by_alice, sent_by_alice, sent_by_bob, received_by_bob) = futures::join!(
+            alice.receive_message(),
+            alice.send_message(b"from alice".to_vec()),
+            bob.send_message(b"from bob".to_vec()),
+            bob.receive_message()
+        );
+        sent_by_alice.unwrap();
+        sent_by_bob.unwrap();
+        assert_eq!(received_by_alice.unwrap(), b"from bob");
+        assert_eq!(received_by_bob.unwrap(), b"from alice");
+    }
+}
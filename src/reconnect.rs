@@ -0,0 +1,86 @@
+use std::future::Future;
+
+use gloo_timers::future::TimeoutFuture;
+use js_sys::Function;
+use wasm_bindgen::prelude::*;
+
+use crate::WormholeError;
+
+/// The upper bound for the delay between two attempts, in milliseconds.
+const MAX_DELAY_MS: f64 = 60_000.0;
+
+#[wasm_bindgen]
+#[derive(Clone)]
+/// A policy for retrying to connect to the rendezvous server, with exponential backoff.
+///
+/// Only transient errors (like the connection to the server dropping) are retried.
+/// Errors like a wrong code fail immediately.
+pub struct ReconnectPolicy {
+    max_retries: u32,
+    base_delay_ms: f64,
+    on_retry: Option<Function>,
+}
+
+#[wasm_bindgen]
+impl ReconnectPolicy {
+    #[wasm_bindgen(constructor)]
+    /// Creates a new policy.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_retries` - The maximum number of retries, not counting the initial attempt.
+    /// * `base_delay_ms` - The delay before the first retry, in milliseconds. It doubles with every subsequent retry.
+    pub fn new(max_retries: u32, base_delay_ms: f64) -> Self {
+        Self {
+            max_retries,
+            base_delay_ms,
+            on_retry: None,
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn base_delay_ms(&self) -> f64 {
+        self.base_delay_ms
+    }
+
+    #[wasm_bindgen(setter)]
+    /// Sets a callback that is invoked with `(retry, max_retries)` before each retry, e.g. to show "reconnecting (2/5)".
+    pub fn set_on_retry(&mut self, on_retry: Option<Function>) {
+        self.on_retry = on_retry;
+    }
+
+    /// The delay before the given (one-based) retry.
+    fn delay_ms(&self, retry: u32) -> f64 {
+        (self.base_delay_ms * 2f64.powi(retry as i32 - 1)).clamp(0.0, MAX_DELAY_MS)
+    }
+}
+
+/// Runs the connection attempt, retrying it according to the policy (if any) as long as it fails with a transient error.
+pub(crate) async fn with_retries<F, Fut, T>(policy: Option<&ReconnectPolicy>, mut connect: F) -> Result<T, WormholeError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, WormholeError>>,
+{
+    let mut retry = 0;
+
+    loop {
+        match (connect().await, policy) {
+            (Err(e), Some(policy)) if e.is_retryable() && retry < policy.max_retries => {
+                retry += 1;
+                log::debug!("Connection attempt failed ({e}), retrying ({retry}/{})", policy.max_retries);
+
+                if let Some(on_retry) = &policy.on_retry {
+                    let _ = on_retry.call2(&JsValue::NULL, &retry.into(), &policy.max_retries.into());
+                }
+                let delay_ms = e.retry_after_ms().unwrap_or_else(|| policy.delay_ms(retry));
+                TimeoutFuture::new(delay_ms as u32).await;
+            }
+            (result, _) => return result,
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::CancelToken;
+
+thread_local! {
+    /// The cancellation tokens of the operations that are currently in flight, by session ID.
+    static SESSIONS: RefCell<BTreeMap<u32, CancelToken>> = const { RefCell::new(BTreeMap::new()) };
+    /// The ID of the next session.
+    static NEXT_ID: Cell<u32> = const { Cell::new(0) };
+}
+
+/// An in-flight connect or transfer, which is listed by `active_sessions` until it is dropped.
+pub(crate) struct Session {
+    id: u32,
+    token: CancelToken,
+}
+
+impl Session {
+    /// Registers an operation, which can then be cancelled with either its own token (if any) or `cancel_all`.
+    pub(crate) fn register(token: Option<&CancelToken>) -> Self {
+        let token = token.cloned().unwrap_or_default();
+        let id = NEXT_ID.with(|next_id| next_id.replace(next_id.get().wrapping_add(1)));
+        SESSIONS.with(|sessions| sessions.borrow_mut().insert(id, token.clone()));
+
+        Self { id, token }
+    }
+
+    /// The token that the operation has to be cancelled with.
+    pub(crate) fn token(&self) -> Option<&CancelToken> {
+        Some(&self.token)
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        SESSIONS.with(|sessions| sessions.borrow_mut().remove(&self.id));
+    }
+}
+
+/// Whether the token (or a clone of it) was passed to an operation that is still in flight.
+pub(crate) fn is_registered(token: &CancelToken) -> bool {
+    SESSIONS.with(|sessions| sessions.borrow().values().any(|registered| registered.same_as(token)))
+}
+
+#[wasm_bindgen]
+/// Returns the IDs of the connects and transfers that are currently in flight.
+///
+/// This only covers the operations started from this instance of the module.
+pub fn active_sessions() -> Vec<u32> {
+    SESSIONS.with(|sessions| sessions.borrow().keys().copied().collect())
+}
+
+#[wasm_bindgen]
+/// Cancels all connects and transfers that are currently in flight, e.g. when the user leaves the page.
+///
+/// The pending operations reject with a cancellation error, as if their `CancelToken` was cancelled; tokens passed
+/// to them are cancelled as well. This only covers the operations started from this instance of the module.
+pub fn cancel_all() {
+    let tokens: Vec<CancelToken> = SESSIONS.with(|sessions| sessions.borrow().values().cloned().collect());
+    for token in tokens {
+        token.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sessions_are_listed_until_dropped() {
+        let first = Session::register(None);
+        let second = Session::register(None);
+        assert_eq!(active_sessions(), [first.id, second.id]);
+
+        drop(first);
+        assert_eq!(active_sessions(), [second.id]);
+
+        drop(second);
+        assert!(active_sessions().is_empty());
+    }
+
+    #[test]
+    fn tokens_are_registered_until_the_session_is_dropped() {
+        let token = CancelToken::new();
+        let session = Session::register(Some(&token));
+        assert!(is_registered(&token));
+        assert!(token.is_active());
+
+        drop(session);
+        assert!(!is_registered(&token));
+        assert!(!token.is_active());
+    }
+
+    #[test]
+    fn cancel_all_cancels_the_registered_tokens() {
+        let token = CancelToken::new();
+        let _session = Session::register(Some(&token));
+        let unregistered = CancelToken::new();
+
+        cancel_all();
+        assert!(token.is_cancelled());
+        assert!(!unregistered.is_cancelled());
+    }
+}
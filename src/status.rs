@@ -0,0 +1,182 @@
+use std::future::Future;
+
+use js_sys::Date;
+use wasm_bindgen::prelude::*;
+
+use crate::{json_to_js, AppConfig, TransferStats, WormholeError, WormholeWelcome};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// A step in the lifecycle of a wormhole, as reported to the status callback of `AppConfig`.
+pub(crate) enum WormholeStatus {
+    /// Connecting to the rendezvous server.
+    Connecting,
+    /// Connected with a generated code, waiting for the peer to enter it.
+    WaitingForPeer,
+    /// The handshake with the peer completed.
+    PeerConnected,
+    /// A transfer started.
+    Transferring,
+    /// The transfer finished successfully.
+    Done,
+    /// Connecting or transferring failed (or was cancelled).
+    Failed,
+}
+
+impl WormholeStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Connecting => "Connecting",
+            Self::WaitingForPeer => "WaitingForPeer",
+            Self::PeerConnected => "PeerConnected",
+            Self::Transferring => "Transferring",
+            Self::Done => "Done",
+            Self::Failed => "Failed",
+        }
+    }
+
+    /// The name of the matching milestone, as reported to the event callback of `AppConfig`.
+    fn event(self) -> &'static str {
+        match self {
+            Self::Connecting => "connecting",
+            Self::WaitingForPeer => "waiting_for_peer",
+            Self::PeerConnected => "peer_connected",
+            Self::Transferring => "transfer_started",
+            Self::Done => "transfer_finished",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+impl AppConfig {
+    /// Invokes the status callback, if any, with the given status, and reports the matching event.
+    pub(crate) fn report_status(&self, status: WormholeStatus) {
+        self.report_status_with(status, serde_json::json!({}));
+    }
+
+    /// Like `report_status`, with the detail of the event.
+    fn report_status_with(&self, status: WormholeStatus, detail: serde_json::Value) {
+        if let Some(callback) = &self.status_callback {
+            // Exceptions thrown by the callback are deliberately ignored, so that they can't abort the operation.
+            let _ = callback.call1(&JsValue::NULL, &status.as_str().into());
+        }
+        self.report_event(status.event(), detail);
+    }
+
+    /// Invokes the event callback, if any, with a `{ event, timestamp, detail }` object.
+    pub(crate) fn report_event(&self, event: &str, detail: serde_json::Value) {
+        if let Some(callback) = &self.event_callback {
+            let event = serde_json::json!({
+                "event": event,
+                "timestamp": Date::now(),
+                "detail": detail,
+            });
+            let _ = callback.call1(&JsValue::NULL, &json_to_js(&event));
+        }
+    }
+
+    /// Reports the `code_generated` event, without the secret words of the code.
+    pub(crate) fn report_code(&self, welcome: &WormholeWelcome) {
+        let (nameplate, words) = welcome.code.split_once('-').unwrap_or((&welcome.code, ""));
+        let detail = serde_json::json!({
+            "nameplate": nameplate,
+            "code_length": words.split('-').count(),
+        });
+        self.report_event("code_generated", detail);
+    }
+
+    /// Invokes the message of the day callback, if any, with the welcome message, if any.
+    pub(crate) fn report_motd(&self, welcome: &WormholeWelcome) {
+        if let (Some(callback), Some(motd)) = (&self.motd_callback, &welcome.welcome) {
+            let _ = callback.call1(&JsValue::NULL, &motd.into());
+        }
+    }
+
+    /// Reports the `bytes_transferred` event with the statistics of a finished transfer.
+    pub(crate) fn report_transferred(&self, stats: &TransferStats) {
+        let detail = serde_json::json!({
+            "bytes": stats.bytes(),
+            "duration_ms": stats.duration_ms(),
+        });
+        self.report_event("bytes_transferred", detail);
+    }
+}
+
+/// Awaits a step of the lifecycle, then reports `on_success` if it succeeded, and `Failed` otherwise.
+pub(crate) async fn track<F, T, E>(config: &AppConfig, future: F, on_success: WormholeStatus) -> Result<T, WormholeError>
+where
+    F: Future<Output = Result<T, E>>,
+    WormholeError: From<E>,
+{
+    let result = track_failure(config, future).await;
+    if result.is_ok() {
+        config.report_status(on_success);
+    }
+
+    result
+}
+
+/// Awaits a step of the lifecycle, and reports `Failed` if it failed. Reporting the success is left to the caller.
+pub(crate) async fn track_failure<F, T, E>(config: &AppConfig, future: F) -> Result<T, WormholeError>
+where
+    F: Future<Output = Result<T, E>>,
+    WormholeError: From<E>,
+{
+    let result = future.await.map_err(WormholeError::from);
+    if let Err(e) = &result {
+        let detail = serde_json::json!({
+            "kind": format!("{:?}", e.kind()),
+            "message": e.to_string(),
+        });
+        config.report_status_with(WormholeStatus::Failed, detail);
+    }
+
+    result
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod peer_tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use js_sys::{Function, Reflect};
+    use wasm_bindgen::closure::Closure;
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+    use crate::test_support::{connect_pair_with, local_config};
+
+    #[wasm_bindgen_test]
+    #[ignore = "needs a local rendezvous server and transit relay, see `test_support`"]
+    async fn events_follow_a_successful_transfer() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let recorded = events.clone();
+        let callback = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+            let name = Reflect::get(&event, &"event".into()).ok().and_then(|name| name.as_string());
+            recorded.borrow_mut().push(name.unwrap_or_default());
+        });
+        let mut sender_config = local_config();
+        sender_config.set_event_callback(Some(callback.as_ref().unchecked_ref::<Function>().clone()));
+
+        let (sender, receiver) = connect_pair_with(&sender_config, &local_config()).await;
+        let (stats, received) = futures::join!(
+            sender.send_file("hello.txt".into(), b"hello".to_vec(), None, None),
+            receiver.receive_file(None, None, None)
+        );
+        stats.unwrap();
+        received.unwrap();
+
+        assert_eq!(
+            *events.borrow(),
+            [
+                "connecting",
+                "code_generated",
+                "waiting_for_peer",
+                "peer_connected",
+                "transfer_started",
+                "transfer_finished",
+                "bytes_transferred",
+            ]
+        );
+    }
+}
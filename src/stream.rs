@@ -0,0 +1,384 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::channel::mpsc;
+use futures::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use futures::{ready, SinkExt, StreamExt, TryStreamExt};
+use js_sys::{Date, Function, Reflect, Uint8Array};
+use magic_wormhole::transfer::{self, TransferError};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::{File, ReadableStream, WritableStream};
+
+use crate::cancel::cancellable_transfer;
+use crate::session::Session;
+use crate::status::{track, WormholeStatus};
+use crate::throttle::Throttled;
+use crate::transfer::{progress_handler, LimitedWriter, TransferStats};
+use crate::{CancelToken, TransferOffer, Wormhole, WormholeError};
+
+/// The number of chunks that are buffered before the transfer waits for the consumer of the stream to catch up.
+const STREAM_BUFFER_CHUNKS: usize = 16;
+
+type Chunk = Result<JsValue, JsValue>;
+
+/// An `AsyncWrite` that forwards the written chunks into a bounded channel, applying backpressure once it is full.
+struct ChannelWriter(mpsc::Sender<Chunk>);
+
+impl ChannelWriter {
+    fn closed() -> io::Error {
+        io::Error::new(io::ErrorKind::BrokenPipe, "the stream was closed by its consumer")
+    }
+}
+
+impl AsyncWrite for ChannelWriter {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        ready!(self.0.poll_ready(cx)).map_err(|_| Self::closed())?;
+        self.0.start_send(Ok(Uint8Array::from(buf).into())).map_err(|_| Self::closed())?;
+
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.0.close_channel();
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// An `AsyncRead` that fails if the wrapped reader yields fewer or more bytes than declared, rather than hanging or
+/// silently sending a truncated file.
+struct SizedReader<R> {
+    inner: R,
+    remaining: u64,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for SizedReader<R> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        let read = ready!(Pin::new(&mut self.inner).poll_read(cx, buf))? as u64;
+
+        if read == 0 && self.remaining > 0 {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("the stream ended {} bytes short of the declared size", self.remaining),
+            )));
+        }
+        if read > self.remaining {
+            return Poll::Ready(Err(io::Error::new(io::ErrorKind::InvalidData, "the stream is longer than the declared size")));
+        }
+        self.remaining -= read;
+
+        Poll::Ready(Ok(read as usize))
+    }
+}
+
+/// Adapts a JS `ReadableStream` of `Uint8Array` chunks into an `AsyncRead`.
+pub(crate) fn stream_reader(stream: ReadableStream) -> impl AsyncRead + Unpin {
+    wasm_streams::ReadableStream::from_raw(stream)
+        .into_stream()
+        .map(|chunk| {
+            chunk
+                .map(|chunk| Uint8Array::new(&chunk).to_vec())
+                .map_err(|e| io::Error::other(format!("failed to read from the stream: {e:?}")))
+        })
+        .boxed_local()
+        .into_async_read()
+}
+
+/// Converts a size passed from JS into a byte count.
+pub(crate) fn parse_size(size: f64) -> Result<u64, WormholeError> {
+    if size.is_finite() && size >= 0.0 && size.fract() == 0.0 {
+        Ok(size as u64)
+    } else {
+        Err(WormholeError::InvalidConfig(format!("invalid size `{size}`: expected a non-negative integer")))
+    }
+}
+
+#[wasm_bindgen]
+/// A file that is being received from the peer as a `ReadableStream` of `Uint8Array` chunks.
+///
+/// The transfer runs in the background for as long as the stream is alive. Freeing this handle doesn't stop it;
+/// cancel the stream (e.g. with `stream.cancel()`) to abort the transfer and release the connection.
+pub struct ReadableStreamHandle {
+    file_name: String,
+    file_size: u64,
+    stream: ReadableStream,
+}
+
+#[wasm_bindgen]
+impl ReadableStreamHandle {
+    #[wasm_bindgen(getter)]
+    pub fn file_name(&self) -> String {
+        self.file_name.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    /// The size of the file, as offered by the peer.
+    pub fn file_size(&self) -> f64 {
+        self.file_size as f64
+    }
+
+    #[wasm_bindgen(getter)]
+    /// The contents of the file.
+    ///
+    /// The stream errors if the transfer fails, and cancelling it aborts the transfer.
+    pub fn stream(&self) -> ReadableStream {
+        self.stream.clone()
+    }
+}
+
+#[wasm_bindgen]
+/// The metadata of a file that was received into a stream with `TransferOffer::accept_to`.
+pub struct ReceivedFileMeta {
+    file_name: String,
+    file_size: u64,
+}
+
+#[wasm_bindgen]
+impl ReceivedFileMeta {
+    #[wasm_bindgen(getter)]
+    pub fn file_name(&self) -> String {
+        self.file_name.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn file_size(&self) -> f64 {
+        self.file_size as f64
+    }
+}
+
+#[wasm_bindgen]
+impl TransferOffer {
+    #[wasm_bindgen]
+    /// Accept the offer, and receive the file as a stream.
+    ///
+    /// Unlike `accept`, the file isn't buffered in memory: the transfer only progresses as fast as the stream is read.
+    /// The stream errors with `SizeMismatch` if the peer doesn't send exactly the offered number of bytes. This consumes
+    /// the offer.
+    ///
+    /// # Arguments
+    ///
+    /// * `progress` - An optional callback, invoked with `(received, total)` as the transfer progresses.
+    /// * `cancel` - An optional token to cancel the transfer with, which errors the stream. The peer is notified that
+    ///   the transfer was aborted.
+    pub fn accept_stream(self, progress: Option<Function>, cancel: Option<&CancelToken>) -> ReadableStreamHandle {
+        let file_name = self.file_name();
+        let file_size = self.request.filesize;
+        let (sender, receiver) = mpsc::channel(STREAM_BUFFER_CHUNKS);
+        let session = Session::register(cancel);
+
+        spawn_local(async move {
+            let cancel = session.token();
+            let mut writer = Throttled::new(LimitedWriter::for_offer(ChannelWriter(sender.clone()), file_size, self.max_bytes), self.config.max_bytes_per_second);
+            let started = Date::now();
+            self.config.report_status(WormholeStatus::Transferring);
+            let receive = |cancelled| {
+                self.request
+                    .accept(self.config.transit_handler(), progress_handler(progress), &mut writer, cancelled)
+            };
+            let result = track(&self.config, cancellable_transfer(receive, cancel), WormholeStatus::Done).await;
+
+            match writer.into_inner().check_size(result, file_size) {
+                Ok(()) => self.config.report_transferred(&TransferStats::since(file_size, started)),
+                Err(e) => {
+                    // This only fails if the consumer already cancelled the stream.
+                    let mut sender = sender;
+                    let _ = sender.send(Err(e.into())).await;
+                }
+            }
+        });
+
+        ReadableStreamHandle {
+            file_name,
+            file_size,
+            stream: wasm_streams::ReadableStream::from_stream(receiver).into_raw(),
+        }
+    }
+
+    #[wasm_bindgen]
+    /// Accept the offer, and write the file into the given stream, e.g. a `FileSystemWritableFileStream` to save it
+    /// straight to disk.
+    ///
+    /// The file is never buffered in memory: the transfer only progresses as fast as the sink accepts the chunks.
+    /// The sink is closed once the whole file was written. If the transfer fails, or the sink errors (e.g. because
+    /// the user cancelled saving the file), the sink is aborted and the peer is notified. This consumes the offer.
+    ///
+    /// # Arguments
+    ///
+    /// * `sink` - The stream to write the file into. It must accept `Uint8Array` chunks, and not be locked.
+    /// * `progress` - An optional callback, invoked with `(received, total)` as the transfer progresses.
+    /// * `cancel` - An optional token to cancel the transfer with.
+    pub async fn accept_to(self, sink: WritableStream, progress: Option<Function>, cancel: Option<&CancelToken>) -> Result<ReceivedFileMeta, WormholeError> {
+        let session = Session::register(cancel);
+        let cancel = session.token();
+        let file_name = self.file_name();
+        let file_size = self.request.filesize;
+        let sink_writer = wasm_streams::WritableStream::from_raw(sink.clone())
+            .try_into_async_write()
+            .map_err(|_| TransferError::from(io::Error::other("the sink is locked")))?;
+        let mut writer = Throttled::new(LimitedWriter::for_offer(sink_writer, file_size, self.max_bytes), self.config.max_bytes_per_second);
+
+        let started = Date::now();
+        self.config.report_status(WormholeStatus::Transferring);
+        let receive = |cancelled| {
+            self.request
+                .accept(self.config.transit_handler(), progress_handler(progress), &mut writer, cancelled)
+        };
+        let result = track(&self.config, cancellable_transfer(receive, cancel), WormholeStatus::Done).await;
+        let mut writer = writer.into_inner();
+        let result = match writer.check_size(result, file_size) {
+            Ok(()) => writer.inner.close().await.map_err(|e| TransferError::from(e).into()),
+            Err(e) => Err(e),
+        };
+
+        if let Err(e) = result {
+            // The sink has to be released before it can be aborted. Aborting fails if it already errored, which is fine.
+            drop(writer);
+            let _ = JsFuture::from(sink.abort()).await;
+            return Err(e);
+        }
+        self.config.report_transferred(&TransferStats::since(file_size, started));
+
+        Ok(ReceivedFileMeta { file_name, file_size })
+    }
+}
+
+#[wasm_bindgen]
+impl Wormhole {
+    #[wasm_bindgen]
+    /// Receive a file from the peer as a stream.
+    ///
+    /// The offer is accepted automatically. Use `request_offer` and `TransferOffer::accept_stream` instead
+    /// to inspect the offer before accepting it.
+    ///
+    /// This consumes the wormhole, as the transfer takes ownership of the connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `progress` - An optional callback, invoked with `(received, total)` as the transfer progresses.
+    /// * `max_bytes` - An optional size limit, in bytes. See `request_offer`.
+    /// * `cancel` - An optional token to cancel waiting for the offer, or the transfer, with.
+    pub async fn receive_file_stream(self, progress: Option<Function>, max_bytes: Option<f64>, cancel: Option<&CancelToken>) -> Result<ReadableStreamHandle, WormholeError> {
+        Ok(self.request_offer(max_bytes, cancel).await?.accept_stream(progress, cancel))
+    }
+
+    #[wasm_bindgen]
+    /// Receive a file from the peer, writing it into the given stream, e.g. to save it straight to disk.
+    ///
+    /// The offer is accepted automatically. Use `request_offer` and `TransferOffer::accept_to` instead
+    /// to inspect the offer before accepting it.
+    ///
+    /// This consumes the wormhole, as the transfer takes ownership of the connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `sink` - The stream to write the file into. See `TransferOffer::accept_to`.
+    /// * `progress` - An optional callback, invoked with `(received, total)` as the transfer progresses.
+    /// * `max_bytes` - An optional size limit, in bytes. See `request_offer`.
+    /// * `cancel` - An optional token to cancel waiting for the offer, or the transfer, with.
+    pub async fn receive_file_to(
+        self,
+        sink: WritableStream,
+        progress: Option<Function>,
+        max_bytes: Option<f64>,
+        cancel: Option<&CancelToken>,
+    ) -> Result<ReceivedFileMeta, WormholeError> {
+        self.request_offer(max_bytes, cancel).await?.accept_to(sink, progress, cancel).await
+    }
+
+    #[wasm_bindgen]
+    /// Send a file to the peer from a stream, e.g. the one returned by `Blob.stream()`.
+    ///
+    /// Unlike `send_file`, the file isn't buffered in memory: chunks are forwarded as the stream produces them.
+    /// The transfer fails if the stream yields fewer or more bytes than `total_size`.
+    ///
+    /// Resolves with the statistics of the transfer once it finished. This isn't just once the stream was consumed:
+    /// the remaining buffered bytes are flushed, and the promise only resolves after the receiver acknowledged the
+    /// complete file, so there is no separate step to finalize the transfer.
+    ///
+    /// This consumes the wormhole, as the transfer takes ownership of the connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_name` - The name of the file, as it will be offered to the peer.
+    /// * `total_size` - The size of the file, in bytes.
+    /// * `stream` - A `ReadableStream` of `Uint8Array` chunks.
+    /// * `progress` - An optional callback, invoked with `(sent, total)` as the transfer progresses.
+    /// * `cancel` - An optional token to cancel the transfer with. The peer is notified that the transfer was aborted.
+    pub async fn send_file_stream(
+        self,
+        file_name: String,
+        total_size: f64,
+        stream: ReadableStream,
+        progress: Option<Function>,
+        cancel: Option<&CancelToken>,
+    ) -> Result<TransferStats, WormholeError> {
+        let session = Session::register(cancel);
+        let cancel = session.token();
+        self.verify(cancel).await?;
+        let file_size = parse_size(total_size)?;
+        let mut file = Throttled::new(
+            SizedReader {
+                inner: stream_reader(stream),
+                remaining: file_size,
+            },
+            self.config.max_bytes_per_second,
+        );
+
+        let relay_hints = self.config.relay_hints()?;
+        let started = Date::now();
+        self.config.report_status(WormholeStatus::Transferring);
+        let send = |cancelled| {
+            transfer::send_file(
+                self.inner.into_inner(),
+                relay_hints,
+                &mut file,
+                file_name,
+                file_size,
+                self.config.transit_abilities(),
+                self.config.transit_handler(),
+                progress_handler(progress),
+                cancelled,
+            )
+        };
+
+        track(&self.config, cancellable_transfer(send, cancel), WormholeStatus::Done).await?;
+
+        let stats = TransferStats::since(file_size, started);
+        self.config.report_transferred(&stats);
+
+        Ok(stats)
+    }
+
+    #[wasm_bindgen]
+    /// Send a browser `File` (e.g. from a file input or a drop event) to the peer.
+    ///
+    /// The file name and size are taken from the `File`, and its contents are streamed with `File.stream()`, so the file
+    /// isn't buffered in memory. In environments without `Blob.stream()`, the contents are read with `arrayBuffer()` instead.
+    ///
+    /// Resolves with the statistics of the transfer once it finished.
+    ///
+    /// This consumes the wormhole, as the transfer takes ownership of the connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The file to send.
+    /// * `progress` - An optional callback, invoked with `(sent, total)` as the transfer progresses.
+    /// * `cancel` - An optional token to cancel the transfer with. The peer is notified that the transfer was aborted.
+    pub async fn send_web_file(self, file: File, progress: Option<Function>, cancel: Option<&CancelToken>) -> Result<TransferStats, WormholeError> {
+        let has_stream = Reflect::get(&file, &"stream".into()).is_ok_and(|stream| stream.is_function());
+        if has_stream {
+            return self.send_file_stream(file.name(), file.size(), file.stream(), progress, cancel).await;
+        }
+
+        let buffer = JsFuture::from(file.array_buffer())
+            .await
+            .map_err(|e| TransferError::from(io::Error::other(format!("failed to read the file `{}`: {e:?}", file.name()))))?;
+        self.send_file(file.name(), Uint8Array::new(&buffer).to_vec(), progress, cancel).await
+    }
+}
@@ -0,0 +1,47 @@
+//! Helpers for the tests that connect two peers.
+//!
+//! They need a local rendezvous server and a websocket transit relay, so they are ignored by default. Start e.g.
+//! `magic-wormhole-mailbox-server` and `magic-wormhole-transit-relay` (with websockets enabled), then run
+//! `wasm-pack test --node -- --include-ignored`. The URLs default to `ws://localhost:4000/v1` and
+//! `ws://localhost:4002`, and can be overridden at build time with `WORMHOLE_TEST_RENDEZVOUS_URL` and
+//! `WORMHOLE_TEST_TRANSIT_RELAY_URL`.
+
+use crate::{default_app_id, AppConfig, Wormhole};
+
+const RENDEZVOUS_URL: &str = match option_env!("WORMHOLE_TEST_RENDEZVOUS_URL") {
+    Some(url) => url,
+    None => "ws://localhost:4000/v1",
+};
+const TRANSIT_RELAY_URL: &str = match option_env!("WORMHOLE_TEST_TRANSIT_RELAY_URL") {
+    Some(url) => url,
+    None => "ws://localhost:4002",
+};
+
+/// Returns a configuration that uses the local rendezvous server and transit relay.
+pub(crate) fn local_config() -> AppConfig {
+    let mut config = AppConfig::new(default_app_id(), RENDEZVOUS_URL.into()).expect("invalid test rendezvous URL");
+    config.set_transit_relay_url(TRANSIT_RELAY_URL.into()).expect("invalid test transit relay URL");
+
+    config
+}
+
+/// Connects two peers with a generated code, and returns the wormholes of the sender and the receiver.
+pub(crate) async fn connect_pair(config: &AppConfig) -> (Wormhole, Wormhole) {
+    connect_pair_with(config, config).await
+}
+
+/// Like `connect_pair`, with a different configuration for each peer.
+pub(crate) async fn connect_pair_with(sender_config: &AppConfig, receiver_config: &AppConfig) -> (Wormhole, Wormhole) {
+    let welcome_and_handshake = Wormhole::connect_without_code(sender_config, 2, None, None, None)
+        .await
+        .expect("failed to connect without a code");
+    let code = welcome_and_handshake.welcome().code();
+    let (sender, receiver) = futures::join!(
+        welcome_and_handshake.finish(None),
+        Wormhole::connect_with_code(receiver_config, &code, None, None, None, None)
+    );
+    let (_, sender) = sender.and_then(|sender| sender.into_parts()).expect("the sender failed to connect");
+    let (_, receiver) = receiver.and_then(|receiver| receiver.into_parts()).expect("the receiver failed to connect");
+
+    (sender, receiver)
+}
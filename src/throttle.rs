@@ -0,0 +1,247 @@
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll};
+
+use futures::channel::oneshot;
+use futures::io::{AsyncRead, AsyncWrite};
+use futures::ready;
+use js_sys::Date;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    /// The global `setTimeout`, which is available in windows, workers and Node.js alike.
+    #[wasm_bindgen(js_name = setTimeout)]
+    fn set_timeout(callback: JsValue, ms: u32) -> JsValue;
+}
+
+/// Returns a future that resolves after `ms` milliseconds.
+///
+/// Unlike a `TimeoutFuture`, it is `Send`, as the readers of folder offers have to be. Dropping it doesn't clear the
+/// timer, which then fires without effect.
+fn sleep(ms: u32) -> oneshot::Receiver<()> {
+    let (sender, receiver) = oneshot::channel();
+    let fire = Closure::once_into_js(move || {
+        let _ = sender.send(());
+    });
+    set_timeout(fire, ms);
+
+    receiver
+}
+
+/// A token bucket, which lets through up to `rate` bytes per second, with bursts of up to one second worth of bytes.
+struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    last_refill_ms: f64,
+    delay: Option<oneshot::Receiver<()>>,
+}
+
+impl TokenBucket {
+    fn new(rate: f64) -> Self {
+        Self::starting_at(rate, Date::now())
+    }
+
+    /// Creates a full bucket at `now_ms`. The rate must be positive.
+    fn starting_at(rate: f64, now_ms: f64) -> Self {
+        // The bucket has to hold at least one byte, so that rates below one byte per second still make progress.
+        let capacity = rate.max(1.0);
+
+        Self {
+            rate,
+            capacity,
+            tokens: capacity,
+            last_refill_ms: now_ms,
+            delay: None,
+        }
+    }
+
+    /// Waits until at least one byte may pass, and returns how many bytes may pass at most.
+    fn poll_allowance(&mut self, cx: &mut Context<'_>) -> Poll<usize> {
+        loop {
+            if let Some(delay) = &mut self.delay {
+                // This only fails if the callback is dropped without being invoked, which the timer never does.
+                let _ = ready!(Pin::new(delay).poll(cx));
+                self.delay = None;
+            }
+
+            match self.allowance_at(Date::now()) {
+                Ok(allowance) => return Poll::Ready(allowance),
+                Err(wait_ms) => self.delay = Some(sleep(wait_ms as u32)),
+            }
+        }
+    }
+
+    /// Refills the bucket at `now_ms`, and returns how many bytes may pass at most, or else how many milliseconds
+    /// to wait until one byte may pass.
+    fn allowance_at(&mut self, now_ms: f64) -> Result<usize, f64> {
+        self.tokens = (self.tokens + (now_ms - self.last_refill_ms) / 1000.0 * self.rate).min(self.capacity);
+        self.last_refill_ms = now_ms;
+        if self.tokens >= 1.0 {
+            return Ok(self.tokens as usize);
+        }
+
+        Err(((1.0 - self.tokens) / self.rate * 1000.0).ceil())
+    }
+
+    fn consume(&mut self, bytes: usize) {
+        self.tokens -= bytes as f64;
+    }
+}
+
+#[derive(Clone)]
+/// A throughput limit, which can be shared by readers or writers that transfer one after the other, like the files
+/// of a folder, so that they are limited as a whole.
+pub(crate) struct Throttle {
+    bucket: Option<Arc<Mutex<TokenBucket>>>,
+}
+
+impl Throttle {
+    /// Creates a limit of `max_bytes_per_second` (if any).
+    pub(crate) fn new(max_bytes_per_second: Option<f64>) -> Self {
+        Self {
+            bucket: max_bytes_per_second.map(|rate| Arc::new(Mutex::new(TokenBucket::new(rate)))),
+        }
+    }
+
+    /// Wraps the reader or writer, limiting it (along with the others wrapped by this limit).
+    pub(crate) fn wrap<T>(&self, inner: T) -> Throttled<T> {
+        Throttled {
+            inner,
+            bucket: self.bucket.clone(),
+        }
+    }
+}
+
+/// An `AsyncRead`/`AsyncWrite` that limits the throughput of the wrapped reader or writer, if a limit is set.
+pub(crate) struct Throttled<T> {
+    inner: T,
+    bucket: Option<Arc<Mutex<TokenBucket>>>,
+}
+
+impl<T> Throttled<T> {
+    /// Wraps the reader or writer, limiting it to `max_bytes_per_second` (if any).
+    pub(crate) fn new(inner: T, max_bytes_per_second: Option<f64>) -> Self {
+        Throttle::new(max_bytes_per_second).wrap(inner)
+    }
+
+    pub(crate) fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Waits until at least one byte may pass, and returns how many of `len` bytes may pass.
+    fn poll_allowance(&mut self, cx: &mut Context<'_>, len: usize) -> Poll<usize> {
+        match &self.bucket {
+            Some(bucket) if len > 0 => lock(bucket).poll_allowance(cx).map(|allowance| allowance.min(len)),
+            _ => Poll::Ready(len),
+        }
+    }
+
+    fn consume(&mut self, bytes: usize) {
+        if let Some(bucket) = &self.bucket {
+            lock(bucket).consume(bytes);
+        }
+    }
+}
+
+/// Locks the bucket. It is never locked across polls, so a panic can't leave it in an inconsistent state.
+fn lock(bucket: &Mutex<TokenBucket>) -> MutexGuard<'_, TokenBucket> {
+    bucket.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for Throttled<R> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        let allowance = ready!(self.poll_allowance(cx, buf.len()));
+        let read = ready!(Pin::new(&mut self.inner).poll_read(cx, &mut buf[..allowance]))?;
+        self.consume(read);
+
+        Poll::Ready(Ok(read))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for Throttled<W> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let allowance = ready!(self.poll_allowance(cx, buf.len()));
+        let written = ready!(Pin::new(&mut self.inner).poll_write(cx, &buf[..allowance]))?;
+        self.consume(written);
+
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bursts_up_to_one_second_worth_of_bytes() {
+        let mut bucket = TokenBucket::starting_at(1000.0, 0.0);
+
+        assert_eq!(bucket.allowance_at(0.0), Ok(1000));
+        assert_eq!(bucket.allowance_at(5000.0), Ok(1000));
+    }
+
+    #[test]
+    fn refills_at_the_rate() {
+        let mut bucket = TokenBucket::starting_at(1000.0, 0.0);
+        bucket.consume(1000);
+
+        assert_eq!(bucket.allowance_at(0.0), Err(1.0));
+        assert_eq!(bucket.allowance_at(250.0), Ok(250));
+        bucket.consume(250);
+        assert_eq!(bucket.allowance_at(500.0), Ok(250));
+    }
+
+    #[test]
+    fn throughput_matches_the_rate() {
+        let mut bucket = TokenBucket::starting_at(100.0, 0.0);
+        let mut now_ms = 0.0;
+        let mut sent = 0;
+
+        // Sending 1100 bytes takes 10 seconds, after the initial burst of 100 bytes.
+        while sent < 1100 {
+            match bucket.allowance_at(now_ms) {
+                Ok(allowance) => {
+                    let bytes = allowance.min(1100 - sent);
+                    bucket.consume(bytes);
+                    sent += bytes;
+                }
+                Err(wait_ms) => now_ms += wait_ms,
+            }
+        }
+
+        assert!((9999.0..=10010.0).contains(&now_ms), "took {now_ms} ms");
+    }
+
+    #[test]
+    fn wrapped_streams_share_the_limit() {
+        let throttle = Throttle {
+            bucket: Some(Arc::new(Mutex::new(TokenBucket::starting_at(1000.0, 0.0)))),
+        };
+        let mut first = throttle.wrap(());
+        let second = throttle.wrap(());
+        first.consume(1000);
+
+        assert_eq!(lock(second.bucket.as_ref().unwrap()).allowance_at(0.0), Err(1.0));
+    }
+
+    #[test]
+    fn slow_rates_still_make_progress() {
+        let mut bucket = TokenBucket::starting_at(0.5, 0.0);
+        bucket.consume(1);
+
+        assert_eq!(bucket.allowance_at(0.0), Err(2000.0));
+        assert_eq!(bucket.allowance_at(2000.0), Ok(1));
+    }
+}
@@ -0,0 +1,540 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::io::{AsyncWrite, Cursor};
+use futures::ready;
+use js_sys::{Date, Function};
+use magic_wormhole::transfer::{self, ReceiveRequest};
+use wasm_bindgen::prelude::*;
+
+use crate::cancel::{cancellable_transfer, cancelled};
+use crate::session::Session;
+use crate::status::{track, WormholeStatus};
+use crate::stream::parse_size;
+use crate::throttle::Throttled;
+use crate::{AppConfig, CancelToken, Wormhole, WormholeError};
+
+#[wasm_bindgen]
+/// A file received from the peer.
+pub struct ReceivedFile {
+    file_name: String,
+    file_size: u64,
+    bytes: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl ReceivedFile {
+    #[wasm_bindgen(getter)]
+    pub fn file_name(&self) -> String {
+        self.file_name.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn file_size(&self) -> f64 {
+        self.file_size as f64
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn bytes(&self) -> Vec<u8> {
+        self.bytes.clone()
+    }
+}
+
+#[wasm_bindgen]
+/// Statistics about a finished transfer.
+pub struct TransferStats {
+    bytes: u64,
+    duration_ms: f64,
+}
+
+impl TransferStats {
+    /// Returns the statistics of a transfer of `bytes` bytes that started at `started_ms` (as per `Date.now()`).
+    pub(crate) fn since(bytes: u64, started_ms: f64) -> Self {
+        Self {
+            bytes,
+            duration_ms: Date::now() - started_ms,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl TransferStats {
+    #[wasm_bindgen(getter)]
+    /// The number of bytes transferred.
+    pub fn bytes(&self) -> f64 {
+        self.bytes as f64
+    }
+
+    #[wasm_bindgen(getter)]
+    /// How long the transfer took, in milliseconds, from starting the transfer (after the handshake) to its completion.
+    pub fn duration_ms(&self) -> f64 {
+        self.duration_ms
+    }
+
+    #[wasm_bindgen(getter)]
+    /// The average throughput of the transfer. It is `0` if the transfer took no measurable time.
+    pub fn bytes_per_second(&self) -> f64 {
+        if self.duration_ms > 0.0 {
+            self.bytes as f64 / (self.duration_ms / 1000.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Wraps an optional JS progress callback into a handler for the transfer functions.
+///
+/// The callback is invoked with the number of bytes transferred so far and the total number of bytes.
+pub(crate) fn progress_handler(progress: Option<Function>) -> impl FnMut(u64, u64) + 'static {
+    move |sent, total| {
+        if let Some(progress) = &progress {
+            // Exceptions thrown by the callback are deliberately ignored, so that they can't abort the transfer.
+            let _ = progress.call2(&JsValue::NULL, &(sent as f64).into(), &(total as f64).into());
+        }
+    }
+}
+
+/// An `AsyncWrite` that fails once more bytes than the limit are written to it, in case the peer sends more than it
+/// offered.
+pub(crate) struct LimitedWriter<W> {
+    pub(crate) inner: W,
+    remaining: Option<u64>,
+    /// The number of bytes the peer sent, including the ones that exceeded the limit.
+    received: u64,
+}
+
+impl<W> LimitedWriter<W> {
+    pub(crate) fn new(inner: W, limit: Option<u64>) -> Self {
+        Self {
+            inner,
+            remaining: limit,
+            received: 0,
+        }
+    }
+
+    /// Creates a writer for a transfer of the offered size, which fails as soon as the peer sends more than it offered,
+    /// or more than the size limit (if any).
+    pub(crate) fn for_offer(inner: W, offered: u64, max_bytes: Option<u64>) -> Self {
+        Self::new(inner, Some(max_bytes.map_or(offered, |max| max.min(offered))))
+    }
+
+    /// Checks the result of a transfer into this writer against the size that the peer offered.
+    ///
+    /// A peer that sends fewer or more bytes than it offered is either broken or malicious, so the transfer fails
+    /// with `SizeMismatch` instead of returning a truncated file.
+    pub(crate) fn check_size<T>(&self, result: Result<T, WormholeError>, expected: u64) -> Result<T, WormholeError> {
+        let size_mismatch = WormholeError::SizeMismatch {
+            expected,
+            actual: self.received,
+        };
+
+        match result {
+            Ok(_) if self.received != expected => Err(size_mismatch),
+            Err(_) if self.received > expected => Err(size_mismatch),
+            result => result,
+        }
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for LimitedWriter<W> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        if self.remaining.is_some_and(|remaining| buf.len() as u64 > remaining) {
+            self.received += buf.len() as u64;
+            return Poll::Ready(Err(io::Error::new(io::ErrorKind::InvalidData, "the peer sent more bytes than the size limit")));
+        }
+        let written = ready!(Pin::new(&mut self.inner).poll_write(cx, buf))?;
+        self.received += written as u64;
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= written as u64;
+        }
+
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+#[wasm_bindgen]
+impl Wormhole {
+    #[wasm_bindgen]
+    /// Send a file to the peer.
+    ///
+    /// Resolves with the statistics of the transfer once the receiver acknowledged the complete file, so that it can
+    /// be shown as delivered. If the receiver failed (e.g. writing the file) or aborted, the promise rejects with a
+    /// `PeerError`, whose message is the one the receiver reported.
+    ///
+    /// This consumes the wormhole, as the transfer takes ownership of the connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_name` - The name of the file, as it will be offered to the peer.
+    /// * `data` - The contents of the file.
+    /// * `progress` - An optional callback, invoked with `(sent, total)` as the transfer progresses.
+    /// * `cancel` - An optional token to cancel the transfer with. The peer is notified that the transfer was aborted.
+    pub async fn send_file(self, file_name: String, data: Vec<u8>, progress: Option<Function>, cancel: Option<&CancelToken>) -> Result<TransferStats, WormholeError> {
+        let session = Session::register(cancel);
+        let cancel = session.token();
+        self.verify(cancel).await?;
+        let file_size = data.len() as u64;
+        let mut file = Throttled::new(Cursor::new(data), self.config.max_bytes_per_second);
+
+        let relay_hints = self.config.relay_hints()?;
+        let started = Date::now();
+        self.config.report_status(WormholeStatus::Transferring);
+        let send = |cancelled| {
+            transfer::send_file(
+                self.inner.into_inner(),
+                relay_hints,
+                &mut file,
+                file_name,
+                file_size,
+                self.config.transit_abilities(),
+                self.config.transit_handler(),
+                progress_handler(progress),
+                cancelled,
+            )
+        };
+
+        track(&self.config, cancellable_transfer(send, cancel), WormholeStatus::Done).await?;
+
+        let stats = TransferStats::since(file_size, started);
+        self.config.report_transferred(&stats);
+
+        Ok(stats)
+    }
+
+    #[wasm_bindgen]
+    /// Generate a code, wait for the peer, and send a file, all in one call.
+    ///
+    /// The lifecycle is as follows:
+    ///
+    /// 1. A code is generated, and `on_code` is invoked with it as soon as it is known, so that it can be shown to the user.
+    /// 2. The call waits for the peer to connect with the code.
+    /// 3. The file is sent, and the returned promise resolves with the code once the transfer finished.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The app configuration.
+    /// * `code_length` - The number of words to include in the generated wormhole code.
+    /// * `file_name` - The name of the file, as it will be offered to the peer.
+    /// * `data` - The contents of the file.
+    /// * `on_code` - A callback, invoked with the generated code.
+    /// * `progress` - An optional callback, invoked with `(sent, total)` as the transfer progresses.
+    pub async fn send_file_with_code(config: &AppConfig, code_length: usize, file_name: String, data: Vec<u8>, on_code: Function, progress: Option<Function>) -> Result<String, WormholeError> {
+        let welcome_and_handshake = Self::connect_without_code(config, code_length, None, None, None).await?;
+        let code = welcome_and_handshake.0.code.clone();
+
+        let _ = on_code.call1(&JsValue::NULL, &code.clone().into());
+        let (_, wormhole) = welcome_and_handshake.finish(None).await?.into_parts()?;
+        wormhole.send_file(file_name, data, progress, None).await?;
+
+        Ok(code)
+    }
+
+    #[wasm_bindgen]
+    /// Connect with a code, and receive a file after confirming the offer, all in one call.
+    ///
+    /// Once the peer offers a file, `on_offer` is invoked with `(file_name, file_size)`.
+    /// If it returns a truthy value, the file is received, and the returned promise resolves with it.
+    /// Otherwise (or if it throws), the offer is declined, and the returned promise resolves with `undefined`.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The app configuration.
+    /// * `code` - The wormhole code.
+    /// * `on_offer` - A callback, invoked with the offered file name and size, which decides whether to accept the offer.
+    /// * `progress` - An optional callback, invoked with `(received, total)` as the transfer progresses.
+    /// * `max_bytes` - An optional size limit, in bytes. Larger offers are rejected before `on_offer` is invoked.
+    pub async fn receive_file_with_code(
+        config: &AppConfig,
+        code: &str,
+        on_offer: Function,
+        progress: Option<Function>,
+        max_bytes: Option<f64>,
+    ) -> Result<Option<ReceivedFile>, WormholeError> {
+        let (_, wormhole) = Self::connect_with_code(config, code, None, None, None, None).await?.into_parts()?;
+        let offer = wormhole.request_offer(max_bytes, None).await?;
+
+        let accepted = on_offer
+            .call2(&JsValue::NULL, &offer.file_name().into(), &offer.file_size().into())
+            .is_ok_and(|accepted| accepted.is_truthy());
+        if !accepted {
+            offer.reject().await?;
+            return Ok(None);
+        }
+
+        Ok(Some(offer.accept(progress, None).await?))
+    }
+
+    #[wasm_bindgen]
+    /// Receive a file from the peer.
+    ///
+    /// The offer is accepted automatically, and the whole file is buffered in memory.
+    /// Use `request_offer` instead to inspect the offer before accepting it.
+    ///
+    /// This consumes the wormhole, as the transfer takes ownership of the connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `progress` - An optional callback, invoked with `(received, total)` as the transfer progresses.
+    /// * `max_bytes` - An optional size limit, in bytes. See `request_offer`.
+    /// * `cancel` - An optional token to cancel waiting for the offer, or the transfer, with.
+    pub async fn receive_file(self, progress: Option<Function>, max_bytes: Option<f64>, cancel: Option<&CancelToken>) -> Result<ReceivedFile, WormholeError> {
+        self.request_offer(max_bytes, cancel).await?.accept(progress, cancel).await
+    }
+
+    #[wasm_bindgen]
+    /// Wait for the peer to offer a file.
+    ///
+    /// The returned offer can be inspected, and must then be either accepted or rejected.
+    ///
+    /// This consumes the wormhole, as the transfer takes ownership of the connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_bytes` - An optional size limit, in bytes. If the offered file is larger, the offer is declined and a
+    ///   `FileTooLarge` error is returned. The limit is also enforced on the bytes actually received once the offer
+    ///   is accepted, in case the peer sends more than it offered.
+    /// * `cancel` - An optional token to cancel waiting for the offer with.
+    pub async fn request_offer(self, max_bytes: Option<f64>, cancel: Option<&CancelToken>) -> Result<TransferOffer, WormholeError> {
+        let session = Session::register(cancel);
+        let cancel = session.token();
+        self.verify(cancel).await?;
+        let max_bytes = max_bytes.map(parse_size).transpose()?;
+        let request = transfer::request_file(
+            self.inner.into_inner(),
+            self.config.relay_hints()?,
+            self.config.transit_abilities(),
+            cancelled(cancel),
+        )
+        .await?
+        .ok_or(WormholeError::Cancelled)?;
+
+        if let Some(max) = max_bytes.filter(|&max| request.filesize > max) {
+            let size = request.filesize;
+            request.reject().await?;
+            return Err(WormholeError::FileTooLarge { size, max });
+        }
+
+        Ok(TransferOffer {
+            request,
+            max_bytes,
+            config: self.config,
+        })
+    }
+}
+
+#[wasm_bindgen]
+/// A file offered by the peer, which can be either accepted or rejected.
+pub struct TransferOffer {
+    pub(crate) request: ReceiveRequest,
+    pub(crate) max_bytes: Option<u64>,
+    /// The configuration that the wormhole was established with.
+    pub(crate) config: AppConfig,
+}
+
+#[wasm_bindgen]
+impl TransferOffer {
+    #[wasm_bindgen(getter)]
+    pub fn file_name(&self) -> String {
+        self.request.filename.to_string_lossy().into_owned()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn file_size(&self) -> f64 {
+        self.request.filesize as f64
+    }
+
+    #[wasm_bindgen(getter)]
+    /// Whether the offer is a directory.
+    ///
+    /// This is always `false` for now, as the transfer layer fails with an "unsupported offer" error on directory offers.
+    pub fn is_directory(&self) -> bool {
+        false
+    }
+
+    #[wasm_bindgen]
+    /// Accept the offer, and receive the file.
+    ///
+    /// The whole file is buffered in memory. The transfer fails with `SizeMismatch` if the peer doesn't send exactly
+    /// the offered number of bytes. This consumes the offer.
+    ///
+    /// # Arguments
+    ///
+    /// * `progress` - An optional callback, invoked with `(received, total)` as the transfer progresses.
+    /// * `cancel` - An optional token to cancel the transfer with. The peer is notified that the transfer was aborted.
+    pub async fn accept(self, progress: Option<Function>, cancel: Option<&CancelToken>) -> Result<ReceivedFile, WormholeError> {
+        let session = Session::register(cancel);
+        let cancel = session.token();
+        let file_name = self.file_name();
+        let file_size = self.request.filesize;
+        let mut writer = Throttled::new(LimitedWriter::for_offer(Vec::new(), file_size, self.max_bytes), self.config.max_bytes_per_second);
+
+        let started = Date::now();
+        self.config.report_status(WormholeStatus::Transferring);
+        let receive = |cancelled| {
+            self.request
+                .accept(self.config.transit_handler(), progress_handler(progress), &mut writer, cancelled)
+        };
+        let result = track(&self.config, cancellable_transfer(receive, cancel), WormholeStatus::Done).await;
+        let writer = writer.into_inner();
+        writer.check_size(result, file_size)?;
+        self.config.report_transferred(&TransferStats::since(file_size, started));
+
+        Ok(ReceivedFile {
+            file_name,
+            file_size,
+            bytes: writer.inner,
+        })
+    }
+
+    #[wasm_bindgen]
+    /// Reject the offer.
+    ///
+    /// The peer is notified that the offer was declined. This consumes the offer.
+    pub async fn reject(self) -> Result<(), WormholeError> {
+        self.request.reject().await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+    use futures::io::AsyncWriteExt;
+
+    use super::*;
+
+    /// Writes the chunks into a writer with the given limit, and returns it along with the result of the last write.
+    fn write_all(limit: Option<u64>, chunks: &[&[u8]]) -> (LimitedWriter<Vec<u8>>, io::Result<()>) {
+        let mut writer = LimitedWriter::new(Vec::new(), limit);
+        let result = block_on(async {
+            for chunk in chunks {
+                writer.write_all(chunk).await?;
+            }
+            Ok(())
+        });
+
+        (writer, result)
+    }
+
+    #[test]
+    fn complete_transfers_pass_the_size_check() {
+        let (writer, result) = write_all(Some(10), &[b"hello", b"world"]);
+        assert!(result.is_ok());
+
+        assert!(matches!(writer.check_size(Ok(()), 10), Ok(())));
+        assert_eq!(writer.inner, b"helloworld");
+    }
+
+    #[test]
+    fn under_delivery_fails_the_size_check() {
+        let (writer, result) = write_all(Some(10), &[b"hello"]);
+        assert!(result.is_ok());
+
+        let result = writer.check_size(Ok(()), 10);
+        assert!(matches!(result, Err(WormholeError::SizeMismatch { expected: 10, actual: 5 })));
+    }
+
+    #[test]
+    fn over_delivery_fails_the_write_and_the_size_check() {
+        let (writer, result) = write_all(Some(5), &[b"hello", b"world"]);
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(writer.inner, b"hello");
+
+        let result = writer.check_size(Err::<(), _>(WormholeError::Cancelled), 5);
+        assert!(matches!(result, Err(WormholeError::SizeMismatch { expected: 5, actual: 10 })));
+    }
+
+    #[test]
+    fn offers_are_limited_to_the_smaller_of_the_offered_size_and_the_limit() {
+        assert_eq!(LimitedWriter::for_offer(Vec::<u8>::new(), 10, None).remaining, Some(10));
+        assert_eq!(LimitedWriter::for_offer(Vec::<u8>::new(), 10, Some(5)).remaining, Some(5));
+        assert_eq!(LimitedWriter::for_offer(Vec::<u8>::new(), 10, Some(20)).remaining, Some(10));
+    }
+
+    #[test]
+    fn other_failures_are_kept() {
+        let (writer, _) = write_all(Some(10), &[b"hello"]);
+
+        let result = writer.check_size(Err::<(), _>(WormholeError::Cancelled), 10);
+        assert!(matches!(result, Err(WormholeError::Cancelled)));
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod peer_tests {
+    use js_sys::Function;
+    use wasm_bindgen::closure::Closure;
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::test_support::{connect_pair, local_config};
+    use crate::{CancelToken, WormholeErrorKind};
+
+    #[wasm_bindgen_test]
+    #[ignore = "needs a local rendezvous server and transit relay, see `test_support`"]
+    async fn send_file_round_trips() {
+        let (sender, receiver) = connect_pair(&local_config()).await;
+        let data = b"hello, wormhole".to_vec();
+
+        let (stats, received) = futures::join!(
+            sender.send_file("hello.txt".into(), data.clone(), None, None),
+            receiver.receive_file(None, None, None)
+        );
+
+        assert_eq!(stats.unwrap().bytes(), data.len() as f64);
+        assert_eq!(received.unwrap().bytes(), data);
+    }
+
+    #[wasm_bindgen_test]
+    #[ignore = "needs a local rendezvous server and transit relay, see `test_support`"]
+    async fn receive_file_returns_the_offer() {
+        let (sender, receiver) = connect_pair(&local_config()).await;
+        let data = vec![0xa5; 100_000];
+
+        let (_, received) = futures::join!(
+            sender.send_file("data.bin".into(), data.clone(), None, None),
+            receiver.receive_file(None, None, None)
+        );
+
+        let received = received.unwrap();
+        assert_eq!(received.file_name(), "data.bin");
+        assert_eq!(received.file_size(), data.len() as f64);
+        assert_eq!(received.bytes(), data);
+    }
+
+    #[wasm_bindgen_test]
+    #[ignore = "needs a local rendezvous server and transit relay, see `test_support`"]
+    async fn cancelling_halfway_fails_both_sides() {
+        let (sender, receiver) = connect_pair(&local_config()).await;
+        let token = CancelToken::new();
+        let halfway = token.clone();
+        let progress = Closure::<dyn FnMut(f64, f64)>::new(move |sent: f64, total: f64| {
+            if sent >= total / 2.0 {
+                halfway.cancel();
+            }
+        });
+
+        let (sent, received) = futures::join!(
+            sender.send_file(
+                "data.bin".into(),
+                vec![0xa5; 1_000_000],
+                Some(progress.as_ref().unchecked_ref::<Function>().clone()),
+                Some(&token)
+            ),
+            receiver.receive_file(None, None, None)
+        );
+
+        assert_eq!(sent.err().unwrap().kind(), WormholeErrorKind::Cancelled);
+        assert_eq!(received.err().unwrap().kind(), WormholeErrorKind::CancelledByPeer);
+    }
+}
@@ -0,0 +1,102 @@
+use std::net::SocketAddr;
+
+use magic_wormhole::transit;
+use wasm_bindgen::prelude::*;
+
+use crate::AppConfig;
+
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+/// How the transit connection of a transfer was established, as reported to the transit callback of `AppConfig`.
+pub struct TransitInfo {
+    is_relay: bool,
+    relay_name: Option<String>,
+}
+
+#[wasm_bindgen]
+impl TransitInfo {
+    #[wasm_bindgen(getter)]
+    /// Whether the data goes through a transit relay, rather than a direct connection to the peer.
+    ///
+    /// This is always the case in browsers, see `Wormhole.can_connect_directly`.
+    pub fn is_relay(&self) -> bool {
+        self.is_relay
+    }
+
+    #[wasm_bindgen(getter)]
+    /// The name of the relay, if the connection goes through a relay that advertised one.
+    pub fn relay_name(&self) -> Option<String> {
+        self.relay_name.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    /// The URL of the relay, if the connection goes through a relay.
+    ///
+    /// This is always `undefined` for now: the transit layer of the pinned wormhole library (rev 46eceb0) only
+    /// reports the name of the relay, not which of its URLs was connected to.
+    pub fn relay_url(&self) -> Option<String> {
+        None
+    }
+
+    #[wasm_bindgen(getter)]
+    /// The round-trip time to the peer, in milliseconds.
+    ///
+    /// This is always `undefined` for now: the transit layer of the pinned wormhole library (rev 46eceb0) doesn't
+    /// measure it.
+    pub fn rtt_ms(&self) -> Option<f64> {
+        None
+    }
+}
+
+impl From<transit::TransitInfo> for TransitInfo {
+    fn from(value: transit::TransitInfo) -> Self {
+        match value {
+            transit::TransitInfo::Relay { name } => Self {
+                is_relay: true,
+                relay_name: name,
+            },
+            _ => Self {
+                is_relay: false,
+                relay_name: None,
+            },
+        }
+    }
+}
+
+impl AppConfig {
+    /// Returns the transit handler of a transfer, which invokes the transit callback, if any, once the transit
+    /// connection is established.
+    pub(crate) fn transit_handler(&self) -> impl FnOnce(transit::TransitInfo, SocketAddr) {
+        let callback = self.transit_callback.clone();
+
+        move |info, _| {
+            if let Some(callback) = callback {
+                // Exceptions thrown by the callback are deliberately ignored, so that they can't abort the transfer.
+                let _ = callback.call1(&JsValue::NULL, &TransitInfo::from(info).into());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relayed_connections_carry_the_relay_name() {
+        let info = TransitInfo::from(transit::TransitInfo::Relay { name: Some("example".into()) });
+
+        assert!(info.is_relay());
+        assert_eq!(info.relay_name().as_deref(), Some("example"));
+        assert_eq!(info.relay_url(), None);
+        assert_eq!(info.rtt_ms(), None);
+    }
+
+    #[test]
+    fn direct_connections_are_not_relayed() {
+        let info = TransitInfo::from(transit::TransitInfo::Direct);
+
+        assert!(!info.is_relay());
+        assert_eq!(info.relay_name(), None);
+    }
+}
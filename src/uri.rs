@@ -0,0 +1,159 @@
+use magic_wormhole::rendezvous::DEFAULT_RENDEZVOUS_SERVER;
+use url::form_urlencoded;
+use url::Url;
+use wasm_bindgen::prelude::*;
+
+use crate::code::parse_code;
+use crate::{AppConfig, WormholeError};
+
+/// The scheme of transfer URIs, as used by the other magic-wormhole clients.
+const URI_SCHEME: &str = "wormhole-transfer";
+/// The query parameter of transfer URIs that overrides the rendezvous server.
+const RENDEZVOUS_PARAM: &str = "rendezvous";
+
+#[wasm_bindgen]
+/// Renders a wormhole code as a `wormhole-transfer:` URI, e.g. to show it as a QR code that the other device scans.
+///
+/// The URI looks like `wormhole-transfer:7-guitarist-revenge`. If the configuration uses another rendezvous server
+/// than the default one, its URL is added as the `rendezvous` query parameter. `uri_to_code` parses it back.
+///
+/// # Arguments
+///
+/// * `config` - The configuration that the code was generated with.
+/// * `code` - The wormhole code. It is normalized with `normalize_code` first.
+pub fn code_to_uri(config: &AppConfig, code: &str) -> Result<String, WormholeError> {
+    let code = parse_code(code)?.to_string();
+    if !code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(WormholeError::InvalidCode {
+            code,
+            reason: "only ASCII letters and digits can be put in a URI".into(),
+        });
+    }
+
+    let mut uri = format!("{URI_SCHEME}:{code}");
+    if config.rendezvous_url != DEFAULT_RENDEZVOUS_SERVER {
+        let query = form_urlencoded::Serializer::new(String::new())
+            .append_pair(RENDEZVOUS_PARAM, &config.rendezvous_url)
+            .finish();
+        uri = format!("{uri}?{query}");
+    }
+
+    Ok(uri)
+}
+
+#[wasm_bindgen]
+/// A `wormhole-transfer:` URI, as parsed by `uri_to_code`.
+pub struct ParsedUri {
+    code: String,
+    rendezvous_url: Option<String>,
+}
+
+#[wasm_bindgen]
+impl ParsedUri {
+    #[wasm_bindgen(getter)]
+    /// The normalized wormhole code.
+    pub fn code(&self) -> String {
+        self.code.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    /// The URL of the rendezvous server to connect to, if the URI overrides the default one.
+    pub fn rendezvous_url(&self) -> Option<String> {
+        self.rendezvous_url.clone()
+    }
+}
+
+#[wasm_bindgen]
+/// Parses a `wormhole-transfer:` URI, as rendered by `code_to_uri`.
+///
+/// Unknown query parameters are ignored, so that URIs of other clients can be parsed too.
+///
+/// # Arguments
+///
+/// * `uri` - The URI, e.g. read from a QR code.
+pub fn uri_to_code(uri: &str) -> Result<ParsedUri, WormholeError> {
+    let invalid = |reason: String| WormholeError::InvalidUri {
+        uri: uri.into(),
+        reason,
+    };
+    let parsed = Url::parse(uri.trim()).map_err(|e| invalid(e.to_string()))?;
+    if parsed.scheme() != URI_SCHEME {
+        return Err(invalid(format!("expected the `{URI_SCHEME}:` scheme")));
+    }
+
+    let code = parse_code(parsed.path()).map_err(|e| invalid(e.to_string()))?;
+    let rendezvous_url = parsed
+        .query_pairs()
+        .find(|(name, _)| name == RENDEZVOUS_PARAM)
+        .map(|(_, url)| url.into_owned());
+    if let Some(url) = &rendezvous_url {
+        AppConfig::validate_rendezvous_url(url)?;
+    }
+
+    Ok(ParsedUri {
+        code: code.to_string(),
+        rendezvous_url,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{default_app_id, default_rendezvous_url};
+
+    fn config(rendezvous_url: &str) -> AppConfig {
+        AppConfig::new_unchecked(default_app_id(), rendezvous_url.into())
+    }
+
+    #[test]
+    fn default_server_round_trips_without_query() {
+        let uri = code_to_uri(&config(&default_rendezvous_url()), "7-Guitarist-Revenge").unwrap();
+        assert_eq!(uri, "wormhole-transfer:7-guitarist-revenge");
+
+        let parsed = uri_to_code(&uri).unwrap();
+        assert_eq!(parsed.code(), "7-guitarist-revenge");
+        assert_eq!(parsed.rendezvous_url(), None);
+    }
+
+    #[test]
+    fn custom_server_round_trips() {
+        let uri = code_to_uri(&config("wss://relay.example.com/v1"), "7-guitarist-revenge").unwrap();
+        assert_eq!(uri, "wormhole-transfer:7-guitarist-revenge?rendezvous=wss%3A%2F%2Frelay.example.com%2Fv1");
+
+        let parsed = uri_to_code(&uri).unwrap();
+        assert_eq!(parsed.code(), "7-guitarist-revenge");
+        assert_eq!(parsed.rendezvous_url().as_deref(), Some("wss://relay.example.com/v1"));
+    }
+
+    #[test]
+    fn unknown_params_are_ignored() {
+        let parsed = uri_to_code("wormhole-transfer:7-guitarist-revenge?role=follower&version=0").unwrap();
+
+        assert_eq!(parsed.code(), "7-guitarist-revenge");
+        assert_eq!(parsed.rendezvous_url(), None);
+    }
+
+    #[test]
+    fn wrong_scheme_is_rejected() {
+        let err = uri_to_code("https://example.com/7-guitarist-revenge").err().unwrap();
+
+        assert!(matches!(err, WormholeError::InvalidUri { .. }));
+    }
+
+    #[test]
+    fn bad_code_is_rejected() {
+        assert!(matches!(uri_to_code("wormhole-transfer:guitarist-revenge"), Err(WormholeError::InvalidUri { .. })));
+        assert!(matches!(uri_to_code("wormhole-transfer:"), Err(WormholeError::InvalidUri { .. })));
+        assert!(matches!(
+            code_to_uri(&config(&default_rendezvous_url()), "7-guitar ist"),
+            Err(WormholeError::InvalidCode { .. })
+        ));
+    }
+
+    #[test]
+    fn invalid_rendezvous_override_is_rejected() {
+        let err = uri_to_code("wormhole-transfer:7-guitarist-revenge?rendezvous=http%3A%2F%2Fexample.com").err().unwrap();
+
+        assert!(matches!(err, WormholeError::RendezvousUrl { .. }));
+    }
+}
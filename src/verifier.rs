@@ -0,0 +1,280 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use futures::channel::oneshot;
+use futures::FutureExt;
+use wasm_bindgen::prelude::*;
+
+use crate::cancel::cancellable;
+use crate::{CancelToken, Wormhole, WormholeError};
+
+/// The number of bytes in each space-separated group of the hex rendering.
+const HEX_GROUP_BYTES: usize = 2;
+/// The emoji that each byte value renders as, see `verifier_emoji`.
+///
+/// These are the emoji from U+1F400 to U+1F4FF (animals, food, objects), except for the ones that default to text
+/// presentation (U+1F43F, U+1F441 and U+1F4FD) and the unassigned U+1F4FE, followed by U+1F680 to U+1F683 (rocket
+/// and vehicles). Each is a single code point that renders as an emoji without a variation selector.
+const EMOJI_TABLE: [char; 256] = [
+    '🐀', '🐁', '🐂', '🐃', '🐄', '🐅', '🐆', '🐇', '🐈', '🐉', '🐊', '🐋', '🐌', '🐍', '🐎', '🐏',
+    '🐐', '🐑', '🐒', '🐓', '🐔', '🐕', '🐖', '🐗', '🐘', '🐙', '🐚', '🐛', '🐜', '🐝', '🐞', '🐟',
+    '🐠', '🐡', '🐢', '🐣', '🐤', '🐥', '🐦', '🐧', '🐨', '🐩', '🐪', '🐫', '🐬', '🐭', '🐮', '🐯',
+    '🐰', '🐱', '🐲', '🐳', '🐴', '🐵', '🐶', '🐷', '🐸', '🐹', '🐺', '🐻', '🐼', '🐽', '🐾', '👀',
+    '👂', '👃', '👄', '👅', '👆', '👇', '👈', '👉', '👊', '👋', '👌', '👍', '👎', '👏', '👐', '👑',
+    '👒', '👓', '👔', '👕', '👖', '👗', '👘', '👙', '👚', '👛', '👜', '👝', '👞', '👟', '👠', '👡',
+    '👢', '👣', '👤', '👥', '👦', '👧', '👨', '👩', '👪', '👫', '👬', '👭', '👮', '👯', '👰', '👱',
+    '👲', '👳', '👴', '👵', '👶', '👷', '👸', '👹', '👺', '👻', '👼', '👽', '👾', '👿', '💀', '💁',
+    '💂', '💃', '💄', '💅', '💆', '💇', '💈', '💉', '💊', '💋', '💌', '💍', '💎', '💏', '💐', '💑',
+    '💒', '💓', '💔', '💕', '💖', '💗', '💘', '💙', '💚', '💛', '💜', '💝', '💞', '💟', '💠', '💡',
+    '💢', '💣', '💤', '💥', '💦', '💧', '💨', '💩', '💪', '💫', '💬', '💭', '💮', '💯', '💰', '💱',
+    '💲', '💳', '💴', '💵', '💶', '💷', '💸', '💹', '💺', '💻', '💼', '💽', '💾', '💿', '📀', '📁',
+    '📂', '📃', '📄', '📅', '📆', '📇', '📈', '📉', '📊', '📋', '📌', '📍', '📎', '📏', '📐', '📑',
+    '📒', '📓', '📔', '📕', '📖', '📗', '📘', '📙', '📚', '📛', '📜', '📝', '📞', '📟', '📠', '📡',
+    '📢', '📣', '📤', '📥', '📦', '📧', '📨', '📩', '📪', '📫', '📬', '📭', '📮', '📯', '📰', '📱',
+    '📲', '📳', '📴', '📵', '📶', '📷', '📸', '📹', '📺', '📻', '📼', '📿', '🚀', '🚁', '🚂', '🚃',
+];
+
+#[wasm_bindgen]
+/// Renders a verifier as lowercase hex, in space-separated groups of four digits (e.g. `3f2a 91c0 …`).
+///
+/// # Arguments
+///
+/// * `verifier` - The verifier bytes, e.g. from `Wormhole.verifier`.
+pub fn verifier_hex(verifier: Vec<u8>) -> String {
+    verifier
+        .chunks(HEX_GROUP_BYTES)
+        .map(|group| group.iter().map(|byte| format!("{byte:02x}")).collect::<String>())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[wasm_bindgen]
+/// Renders a verifier as a string of emoji, one per byte, which is easier to compare at a glance than hex.
+///
+/// # Arguments
+///
+/// * `verifier` - The verifier bytes, e.g. from `Wormhole.verifier`.
+pub fn verifier_emoji(verifier: Vec<u8>) -> String {
+    verifier
+        .iter()
+        .map(|&byte| EMOJI_TABLE[usize::from(byte)])
+        .collect()
+}
+
+#[wasm_bindgen]
+impl Wormhole {
+    #[wasm_bindgen(getter)]
+    /// The verifier of the established wormhole, rendered with `verifier_hex`.
+    pub fn verifier_hex(&self) -> String {
+        verifier_hex(self.verifier())
+    }
+
+    #[wasm_bindgen(getter)]
+    /// The verifier of the established wormhole, rendered with `verifier_emoji`.
+    pub fn verifier_emoji(&self) -> String {
+        verifier_emoji(self.verifier())
+    }
+}
+
+#[wasm_bindgen]
+/// A request to confirm the verifier before a transfer starts, as passed to the verification callback of `AppConfig`.
+pub struct VerificationRequest {
+    verifier: Vec<u8>,
+    sender: Rc<RefCell<Option<oneshot::Sender<bool>>>>,
+}
+
+#[wasm_bindgen]
+impl VerificationRequest {
+    #[wasm_bindgen(getter)]
+    /// The verifier to compare with the peer, see `Wormhole.verifier`.
+    pub fn verifier(&self) -> Vec<u8> {
+        self.verifier.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    /// The verifier, rendered with `verifier_hex`.
+    pub fn verifier_hex(&self) -> String {
+        verifier_hex(self.verifier.clone())
+    }
+
+    #[wasm_bindgen(getter)]
+    /// The verifier, rendered with `verifier_emoji`.
+    pub fn verifier_emoji(&self) -> String {
+        verifier_emoji(self.verifier.clone())
+    }
+
+    #[wasm_bindgen]
+    /// Confirms (or rejects) that the verifier matches the one the peer sees, which lets the transfer proceed (or aborts it).
+    ///
+    /// Only the first call has an effect.
+    ///
+    /// # Arguments
+    ///
+    /// * `ok` - Whether the verifiers match.
+    pub fn confirm_verification(&self, ok: bool) {
+        if let Some(sender) = self.sender.borrow_mut().take() {
+            let _ = sender.send(ok);
+        }
+    }
+}
+
+impl Wormhole {
+    /// Waits for the user to confirm the verifier, if the configuration requires it.
+    ///
+    /// This fails with `VerificationRejected` if the verifier is rejected, or if the request is dropped without an answer,
+    /// and with `Cancelled` if the token (if any) is cancelled while waiting for the answer.
+    pub(crate) async fn verify(&self, cancel: Option<&CancelToken>) -> Result<(), WormholeError> {
+        if !self.config.require_verification {
+            return Ok(());
+        }
+        let callback = self.config.verification_callback.as_ref().ok_or_else(|| {
+            WormholeError::InvalidConfig("verification is required, but no verification callback is set".into())
+        })?;
+
+        let (sender, receiver) = oneshot::channel();
+        let request = VerificationRequest {
+            verifier: self.verifier(),
+            sender: Rc::new(RefCell::new(Some(sender))),
+        };
+        let _ = callback.call1(&JsValue::NULL, &request.into());
+
+        match cancellable(receiver.map(Ok::<_, WormholeError>), cancel).await? {
+            Ok(true) => Ok(()),
+            _ => Err(WormholeError::VerificationRejected),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn verifier_hex_groups_bytes() {
+        assert_eq!(verifier_hex(vec![0x3f, 0x2a, 0x91]), "3f2a 91");
+        assert_eq!(verifier_hex(vec![0x3f, 0x2a, 0x91, 0xc0]), "3f2a 91c0");
+        assert_eq!(verifier_hex(Vec::new()), "");
+    }
+
+    #[test]
+    fn verifier_emoji_maps_each_byte() {
+        assert_eq!(verifier_emoji(vec![0x00, 0x2a, 0x3f, 0x40, 0x91]), "🐀🐪👀👂💓");
+        assert_eq!(verifier_emoji(vec![0xfb, 0xfc, 0xff]), "📿🚀🚃");
+        assert_eq!(verifier_emoji(Vec::new()), "");
+    }
+
+    fn request() -> (VerificationRequest, oneshot::Receiver<bool>) {
+        let (sender, receiver) = oneshot::channel();
+        let request = VerificationRequest {
+            verifier: vec![0x3f, 0x2a],
+            sender: Rc::new(RefCell::new(Some(sender))),
+        };
+
+        (request, receiver)
+    }
+
+    #[test]
+    fn confirming_sends_the_answer() {
+        let (request, mut receiver) = request();
+        request.confirm_verification(true);
+
+        assert_eq!(receiver.try_recv(), Ok(Some(true)));
+    }
+
+    #[test]
+    fn only_the_first_answer_counts() {
+        let (request, mut receiver) = request();
+        request.confirm_verification(false);
+        request.confirm_verification(true);
+
+        assert_eq!(receiver.try_recv(), Ok(Some(false)));
+    }
+
+    #[test]
+    fn dropping_the_request_cancels_the_answer() {
+        let (request, mut receiver) = request();
+        drop(request);
+
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn emoji_table_has_no_duplicates() {
+        let emoji: HashSet<char> = EMOJI_TABLE.into_iter().collect();
+
+        assert_eq!(emoji.len(), EMOJI_TABLE.len());
+    }
+
+    #[test]
+    fn emoji_table_leaves_out_text_presentation_and_unassigned_code_points() {
+        for code_point in ['\u{1F43F}', '\u{1F441}', '\u{1F4FD}', '\u{1F4FE}'] {
+            assert!(!EMOJI_TABLE.contains(&code_point), "{code_point:?}");
+        }
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod peer_tests {
+    use gloo_timers::future::TimeoutFuture;
+    use js_sys::Function;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::test_support::{connect_pair, connect_pair_with, local_config};
+    use crate::{AppConfig, CancelToken, WormholeErrorKind};
+
+    /// Returns a configuration that requires verification, answered by the given JS function body.
+    fn verifying_config(answer: &str) -> AppConfig {
+        let mut config = local_config();
+        config.set_require_verification(true);
+        config.set_verification_callback(Some(Function::new_with_args("request", answer)));
+
+        config
+    }
+
+    #[wasm_bindgen_test]
+    #[ignore = "needs a local rendezvous server and transit relay, see `test_support`"]
+    async fn confirmed_verification_lets_the_transfer_proceed() {
+        let (sender, receiver) = connect_pair_with(&verifying_config("request.confirm_verification(true)"), &local_config()).await;
+
+        let (stats, received) = futures::join!(
+            sender.send_file("hello.txt".into(), b"hello".to_vec(), None, None),
+            receiver.receive_file(None, None, None)
+        );
+        stats.unwrap();
+        received.unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    #[ignore = "needs a local rendezvous server and transit relay, see `test_support`"]
+    async fn rejected_verification_aborts_the_transfer() {
+        let (sender, _receiver) = connect_pair_with(&verifying_config("request.confirm_verification(false)"), &local_config()).await;
+
+        let error = sender.send_file("hello.txt".into(), b"hello".to_vec(), None, None).await.err().unwrap();
+        assert_eq!(error.kind(), WormholeErrorKind::VerificationRejected);
+    }
+
+    #[wasm_bindgen_test]
+    #[ignore = "needs a local rendezvous server and transit relay, see `test_support`"]
+    async fn pending_verification_can_be_cancelled() {
+        // The callback keeps the request alive without answering it.
+        let (sender, _receiver) = connect_pair_with(&verifying_config("globalThis.pendingRequest = request"), &local_config()).await;
+        let cancel = CancelToken::new();
+
+        let (result, ()) = futures::join!(sender.send_file("hello.txt".into(), b"hello".to_vec(), None, Some(&cancel)), async {
+            TimeoutFuture::new(100).await;
+            cancel.cancel();
+        });
+        assert_eq!(result.err().unwrap().kind(), WormholeErrorKind::Cancelled);
+    }
+
+    #[wasm_bindgen_test]
+    #[ignore = "needs a local rendezvous server and transit relay, see `test_support`"]
+    async fn both_peers_see_the_same_verifier() {
+        let (sender, receiver) = connect_pair(&local_config()).await;
+
+        assert!(!sender.verifier().is_empty());
+        assert_eq!(sender.verifier(), receiver.verifier());
+    }
+}